@@ -1,29 +1,39 @@
 use crate::{
     backend::{
-        data::{channel::Channel, channel_list::ChannelList, video::Video},
+        data::{channel::Channel, channel_list::ChannelList, stats::Stats, video::Video},
         io::config::Config,
-        io::{history::History, write_config, FileType::DbFile},
+        io::{archive::Archive, history::History, open_counts::OpenCounts},
         Action,
         Action::*,
+        Error::NewerDbVersion,
+        profile_switcher::ProfileSwitcher,
         Filter, Result, Screen,
         Screen::*,
         Terminal,
     },
     notification::{notify_error, notify_open},
 };
-use std::process::{Command, Stdio};
-use super::data::video::DownloadState;
+use std::{
+    fmt,
+    process::{Command, Stdio},
+    sync::mpsc::Sender,
+    thread,
+    time::{Duration, Instant},
+};
+use super::data::{feed::FeedError, video::DownloadState};
 use log::*;
 
 /// Thread states, printed as-is to user
 #[derive(Clone, Debug)]
 pub enum FetchState {
-    DownloadsFailure(usize),
+    DownloadsFailure(Vec<(String, FeedError)>), // failed urls and why
     Scheduled,
     Loading,
     FetchingDearrow,
     Fetched,
+    FetchedWithWarnings(Vec<String>),
     VideoState(String, DownloadState), // string is video id/url
+    Watched(String, u64), // video url and seconds until the player was closed
 }
 
 impl Default for FetchState {
@@ -32,6 +42,21 @@ impl Default for FetchState {
     }
 }
 
+impl fmt::Display for FetchState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DownloadsFailure(errors) => write!(f, "DownloadsFailure({})", errors.len()),
+            Self::Scheduled => write!(f, "Scheduled"),
+            Self::Loading => write!(f, "Loading"),
+            Self::FetchingDearrow => write!(f, "FetchingDearrow"),
+            Self::Fetched => write!(f, "Fetched"),
+            Self::FetchedWithWarnings(warnings) => write!(f, "Fetched ({} warnings)", warnings.len()),
+            Self::VideoState(_, state) => write!(f, "{:?}", state),
+            Self::Watched(_, seconds) => write!(f, "Watched ({}s)", seconds),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct StateUpdate {
     text: String,
@@ -52,11 +77,26 @@ pub(crate) struct Core {
     pub(crate) current_screen: Screen,
     channel_list: ChannelList,
     pub(crate) playback_history: History,
+    /// videos removed by the retention settings
+    archive: Archive,
+    open_counts: OpenCounts,
+    pub(crate) show_details: bool,
+    pub(crate) show_video_details: bool,
+    pub(crate) details_scroll: u16,
+    /// error shown in the info line until `message_timeout` seconds passed
+    status_message: Option<(String, Instant)>,
+    pub(crate) profile_switcher: Option<ProfileSwitcher>,
+    /// shown in a popup while set, scrolled with `details_scroll`
+    pub(crate) stats: Option<Stats>,
+    /// profile picked in the switcher (`None` is the default profile), tyt restarts with it
+    pub(crate) switch_to_profile: Option<Option<String>>,
+    /// reports when a video player was closed
+    status_sender: Sender<StateUpdate>,
 }
 
 impl Core {
     /// Load core
-    pub(crate) fn load() -> Result<Self> {
+    pub(crate) fn load(status_sender: Sender<StateUpdate>) -> Result<Self> {
         let terminal = Terminal::default();
 
         let config = Config::read()?;
@@ -67,14 +107,24 @@ impl Core {
             Filter::OnlyNew
         };
 
-        let mut channel_list = ChannelList::load().unwrap_or_else(|error| {
-            notify_error(&format!("Could not load DB file: {:?}", error));
-            ChannelList::default()
-        });
+        let mut channel_list = match ChannelList::load(&config) {
+            Ok(channel_list) => channel_list,
+            // the default would be saved over the newer file
+            Err(error @ NewerDbVersion(..)) => {
+                notify_error(&error.to_string());
+                return Err(error);
+            }
+            Err(error) => {
+                notify_error(&format!("Could not load DB file: {:?}", error));
+                ChannelList::default()
+            }
+        };
         channel_list.select(Some(0));
         channel_list.set_filter(current_filter);
 
-        let playback_history = History::load();
+        let playback_history = History::load(config.storage, config.max_history);
+        let archive = Archive::load();
+        let open_counts = OpenCounts::load(playback_history.entries());
 
         let core = Core {
             terminal,
@@ -82,14 +132,66 @@ impl Core {
             current_screen: Channels,
             channel_list,
             playback_history,
+            archive,
+            open_counts,
+            show_details: false,
+            show_video_details: false,
+            details_scroll: 0,
+            status_message: None,
+            profile_switcher: None,
+            stats: None,
+            switch_to_profile: None,
+            status_sender,
         };
 
         Ok(core)
     }
 
+    #[allow(dead_code)]
     pub(crate) fn save(&mut self) {
-        let string = serde_json::to_string(self.channel_list()).unwrap();
-        write_config(DbFile, &string);
+        if let Err(error) = self.channel_list.save(self.config.storage) {
+            self.set_status_message(error.to_string());
+        }
+    }
+
+    /// Store the channel `id` after it was updated
+    /// Store the channel `id`; returns whether it was stored
+    pub(crate) fn save_channel(&mut self, id: &str) -> bool {
+        match self.channel_list.save_channel(id, self.config.storage) {
+            Ok(()) => true,
+            Err(error) => {
+                self.set_status_message(error.to_string());
+                false
+            }
+        }
+    }
+
+    /// Store a single video of the channel `id` after it was changed
+    fn save_video(&mut self, id: &str, video: &Video) {
+        if let Err(error) = self.channel_list.save_video(id, video, self.config.storage) {
+            self.set_status_message(error.to_string());
+        }
+    }
+
+    pub(crate) fn set_status_message(&mut self, message: String) {
+        error!("{}", message);
+        self.status_message = Some((message, Instant::now()));
+    }
+
+    pub(crate) fn status_message(&self) -> Option<&String> {
+        self.status_message.as_ref().map(|(message, _)| message)
+    }
+
+    /// Remove the status message once it timed out; returns whether it was removed
+    pub(crate) fn clear_old_status_message(&mut self) -> bool {
+        let timeout = Duration::from_secs(self.config.message_timeout as u64);
+        match &self.status_message {
+            Some((_, since)) if since.elapsed() >= timeout => {
+                self.status_message = None;
+                true
+            }
+            _ => false,
+        }
     }
 
     /// receive all status updates from status channel
@@ -106,6 +208,33 @@ impl Core {
                     }
                 }
             }
+            FetchState::Watched(video_url, seconds) => {
+                if let Err(error) = self.playback_history.set_watched(&video_url, seconds) {
+                    self.set_status_message(error.to_string());
+                }
+
+                // the history knows the channel of the video
+                let channel_id = self
+                    .playback_history
+                    .entries()
+                    .iter()
+                    .rev()
+                    .find(|entry| entry.link() == &video_url)
+                    .map(|entry| entry.channel_id().to_string());
+                let Some(channel_id) = channel_id else {
+                    return;
+                };
+
+                let stored = self
+                    .channel_list
+                    .get_unfiltered_mut_by_id(&channel_id)
+                    .and_then(|channel| channel.get_mut_by_id(video_url));
+                if let Some(video) = stored {
+                    video.set_open_duration(seconds);
+                    let video = video.clone();
+                    self.save_video(&channel_id, &video);
+                }
+            }
             _ => {
                 if let Some(channel) = self.channel_list.get_unfiltered_mut_by_id(&item.text) {
                     channel.fetch_state = item.state.clone();
@@ -122,10 +251,45 @@ impl Core {
         &self.current_screen
     }
 
+    /// profile switcher or statistics are shown over the channel list
+    pub(crate) fn popup_open(&self) -> bool {
+        self.profile_switcher.is_some() || self.stats.is_some()
+    }
+
     // --- actions -----
 
     /// Contains every possible action.
     pub(crate) fn action(&mut self, action: Action) {
+        // the open profile switcher takes all navigation
+        if let Some(switcher) = self.profile_switcher.as_mut() {
+            match action {
+                Up => switcher.prev(),
+                Down => switcher.next(),
+                Enter => {
+                    self.switch_to_profile = switcher.selected_other();
+                    self.profile_switcher = None;
+                }
+                Leave | SwitchProfile => self.profile_switcher = None,
+                _ => {}
+            }
+            return;
+        }
+
+        if self.stats.is_some() {
+            match action {
+                Up => self.details_scroll = self.details_scroll.saturating_sub(1),
+                Down => self.details_scroll = self.details_scroll.saturating_add(1),
+                Leave | ToggleStats => self.stats = None,
+                _ => {}
+            }
+            return;
+        }
+
+        // a different video is selected, start its description at the top
+        if matches!(action, Mark(_) | Up | Down | Enter | Leave | NextChannel | PrevChannel) {
+            self.details_scroll = 0;
+        }
+
         let _ = || -> Option<()> {
             match action {
                 Mark(state) => {
@@ -134,9 +298,11 @@ impl Core {
                         let current_channel = self.get_selected_channel_mut()?;
                         let selected = current_channel.selected()?;
 
-                        if let Some(video) = current_channel.get_mut(selected) {
+                        let channel_id = current_channel.id().clone();
+                        let video = current_channel.get_mut(selected).map(|video| {
                             video.mark(state);
-                        }
+                            video.clone()
+                        });
 
                         let has_new = !current_channel.has_new();
                         let is_only_new = self.channel_list.get_filter() == Filter::OnlyNew;
@@ -149,31 +315,63 @@ impl Core {
 
                         // let pos = self.get_selected_channel_index();
                         let pos = self.get_selected_channel()?.selected();
-                        self.save();
+                        if let Some(video) = video {
+                            self.save_video(&channel_id, &video);
+                        }
                         self.get_selected_channel_mut().as_mut()?.select(pos);
                     }
                 }
                 Up => match self.current_screen {
                     Channels => self.channel_list.prev(),
                     Videos => self.get_selected_channel_mut()?.prev(),
+                    PlaybackHistory => self.playback_history.prev(),
                 },
                 Down => match self.current_screen {
                     Channels => self.channel_list.next(),
                     Videos => self.get_selected_channel_mut()?.next(),
+                    PlaybackHistory => self.playback_history.next(),
                 },
+                Enter if self.current_screen == PlaybackHistory => self.jump_to_history_entry()?,
                 Enter => {
                     if self.get_selected_channel().is_some() {
                         self.get_selected_channel_mut().unwrap().select(Some(0));
+                        self.show_details = false;
                         self.current_screen = Videos;
                     }
                 }
+                ToggleDetails => match self.current_screen {
+                    Channels => self.show_details = !self.show_details,
+                    Videos => self.show_video_details = !self.show_video_details,
+                    PlaybackHistory => {}
+                },
+                ScrollDetailsDown => {
+                    self.details_scroll = self.details_scroll.saturating_add(1);
+                }
+                ScrollDetailsUp => {
+                    self.details_scroll = self.details_scroll.saturating_sub(1);
+                }
+                ToggleStats => {
+                    self.show_details = false;
+                    self.details_scroll = 0;
+                    self.stats = Some(Stats::collect(
+                        &self.channel_list,
+                        &self.open_counts,
+                        self.playback_history.entries(),
+                        self.config.stale_after_months,
+                    ));
+                }
+                SwitchProfile => {
+                    self.show_details = false;
+                    self.profile_switcher = Some(ProfileSwitcher::new());
+                }
                 Leave => {
+                    self.show_details = false;
                     self.current_screen = Channels;
                     let i = self.get_selected_channel_index();
                     self.channel_list.select(i);
                 }
                 NextChannel => match self.current_screen {
-                    Channels => {}
+                    Channels | PlaybackHistory => {}
                     Videos => {
                         self.action(Leave);
                         self.action(Down);
@@ -181,7 +379,7 @@ impl Core {
                     }
                 },
                 PrevChannel => match self.current_screen {
-                    Channels => {}
+                    Channels | PlaybackHistory => {}
                     Videos => {
                         self.action(Leave);
                         self.action(Up);
@@ -189,41 +387,132 @@ impl Core {
                     }
                 },
                 SetVideoFav => {
+                    let channel_id = self.get_selected_channel()?.id().clone();
                     if let Some(ref mut video) = self.get_selected_video_mut() {
                         video.set_fav(!video.is_fav());
-                        self.save();
+                        let video = video.clone();
+                        self.save_video(&channel_id, &video);
                     }
                 }
+                Open if self.current_screen == PlaybackHistory => {
+                    let entry = self.playback_history.selected()?.clone();
+
+                    // the stored video is marked as if it was opened in its channel
+                    let stored = self
+                        .channel_list
+                        .get_unfiltered_mut_by_id(entry.channel_id())
+                        .and_then(|channel| channel.get_mut_by_id(entry.link().clone()));
+                    if let Some(video) = stored.filter(|_| self.config.mark_on_open) {
+                        video.mark(true);
+                        let video = video.clone();
+                        self.save_video(entry.channel_id(), &video);
+                    }
+
+                    if let Err(error) = self.playback_history.reopen_selected() {
+                        self.set_status_message(error.to_string());
+                    }
+                    if let Err(error) = self.open_counts.add(entry.channel_id()) {
+                        self.set_status_message(error.to_string());
+                    }
+                    self.playback_history.select(Some(0));
+
+                    self.play(entry.link().clone(), entry.title());
+                }
                 Open => {
                     // get video
                     let video = self.get_selected_video_mut()?.clone();
+                    let channel_id = self.get_selected_channel()?.id().clone();
 
                     // mark video
                     if self.config.mark_on_open {
                         self.action(Mark(true));
                     }
 
-                    // call video player
-                    let command = Command::new("setsid")
-                        .arg("-f")
-                        .arg(&self.config.video_player)
-                        .arg(video.link())
-                        .stderr(Stdio::null())
-                        .stdout(Stdio::null())
-                        .spawn();
-
-                    self.playback_history.add(video.clone());
-
-                    match command {
-                        Ok(_) => notify_open(&video.get_details()),
-                        Err(error) => notify_error(&error.to_string()),
-                    };
+                    if let Err(error) = self.playback_history.add(&video, &channel_id) {
+                        self.set_status_message(error.to_string());
+                    }
+                    if let Err(error) = self.open_counts.add(&channel_id) {
+                        self.set_status_message(error.to_string());
+                    }
+
+                    self.play(video.link().clone(), &video.get_details());
+                }
+                FocusHistory => match self.current_screen {
+                    PlaybackHistory => self.action(Leave),
+                    _ => {
+                        self.show_details = false;
+                        self.current_screen = PlaybackHistory;
+                        if self.playback_history.state_mut().selected().is_none() {
+                            self.playback_history.select(Some(0));
+                        }
+                    }
+                },
+                RemoveFromHistory => {
+                    if let Err(error) = self.playback_history.remove_selected() {
+                        self.set_status_message(error.to_string());
+                    }
+                }
+                ClearHistory => {
+                    if let Err(error) = self.playback_history.clear() {
+                        self.set_status_message(error.to_string());
+                    }
                 }
             }
             None
         }();
     }
 
+    /// Start the video player with `link`. Once it is closed, the watched time is reported
+    /// through the status channel.
+    fn play(&mut self, link: String, details: &str) {
+        let command = Command::new("setsid")
+            .arg("-w")
+            .arg(&self.config.video_player)
+            .arg(&link)
+            .stderr(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn();
+
+        let mut child = match command {
+            Ok(child) => child,
+            Err(error) => return notify_error(&error.to_string()),
+        };
+        notify_open(details);
+
+        let sender = self.status_sender.clone();
+        thread::spawn(move || {
+            let started = Instant::now();
+            if child.wait().is_ok() {
+                let seconds = started.elapsed().as_secs();
+                let _ = sender.send(StateUpdate::new(link.clone(), FetchState::Watched(link, seconds)));
+            }
+        });
+    }
+
+    /// Open the channel of the selected history entry with its video selected
+    fn jump_to_history_entry(&mut self) -> Option<()> {
+        let entry = self.playback_history.selected()?.clone();
+
+        // the channel might be hidden because it has no new videos
+        if self.channel_list.get_position_by_id(entry.channel_id()).is_none() {
+            self.channel_list.set_filter(Filter::NoFilter);
+        }
+
+        let Some(position) = self.channel_list.get_position_by_id(entry.channel_id()) else {
+            self.set_status_message(format!("{} is not in the channel list", entry.title()));
+            return None;
+        };
+        self.channel_list.select(Some(position));
+
+        let channel = self.channel_list.get_mut(position)?;
+        let video = channel.videos.iter().position(|video| video.link() == entry.link());
+        channel.select(video.or(Some(0)));
+
+        self.show_details = false;
+        self.current_screen = Videos;
+        Some(())
+    }
+
     // pub(crate) fn draw(&self) {
         // draw(self.into());
     // }
@@ -233,8 +522,25 @@ impl Core {
     }
 
     /// Search for the channel in channel_list by id. If found insert videos that are not already in channel.videos; else insert channel to channel_list.
-    pub(crate) fn update_channel(&mut self, updated_channel: Channel) {
-        self.channel_list.update_channel(updated_channel, self.config.sort_channels);
+    /// Afterwards the retention settings are applied.
+    pub(crate) fn update_channel(&mut self, mut updated_channel: Channel) {
+        // pruned videos stay away, even if the feed still lists them
+        self.archive.remove_archived(&mut updated_channel.videos);
+
+        let id = updated_channel.id().clone();
+        self.channel_list.update_channel(
+            updated_channel,
+            self.config.sort_channels,
+            self.config.filter_stored_videos,
+        );
+
+        let pruned = self.channel_list.prune_channel(&id, &self.config);
+        self.archive.add(&pruned);
+        self.archive.expire(self.config.max_archive_age);
+
+        if let Err(error) = self.archive.save() {
+            self.set_status_message(error.to_string());
+        }
     }
 
     pub(crate) fn get_selected_video_link(&mut self) -> String {
@@ -266,6 +572,11 @@ impl Core {
         self.channel_list.get_mut(i)
     }
 
+    pub(crate) fn get_selected_video(&self) -> Option<&Video> {
+        let channel = self.get_selected_channel()?;
+        channel.videos.get(channel.selected()?)
+    }
+
     pub(crate) fn get_selected_video_mut(&mut self) -> Option<&mut Video> {
         let i = self.get_selected_channel()?.selected()?;
         self.get_selected_channel_mut()?.get_mut(i)
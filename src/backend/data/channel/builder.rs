@@ -2,6 +2,8 @@ use crate::backend::{
     data::{
         channel::Channel,
         feed::Feed,
+        Fetcher,
+        filter::Filter,
         video::{builder::VideoBuilder, Video},
     },
     SortingMethodVideos,
@@ -47,11 +49,11 @@ impl ChannelBuilder {
         self
     }
 
-    pub fn use_dearrow(mut self) -> Self {
+    pub(crate) fn use_dearrow(mut self, fetcher: &Fetcher) -> Self {
 
         for video in self.new_videos.iter_mut() {
             if let Some(id) = video.get_id() {
-                let dearrow_title = dearrow::get_best_title(id);
+                let dearrow_title = dearrow::get_best_title(&fetcher.client, &fetcher.limiter, &fetcher.retry_policy, id);
                 video.set_dearrow_title(dearrow_title);
             }
         }
@@ -59,18 +61,38 @@ impl ChannelBuilder {
         self
     }
 
+    /// Drop new videos that do not pass `filter`, e.g. after dearrow titles are known.
+    /// The links of those that were not stored before are kept, so they are taken out again
+    /// if the channel was already sent with them; stored videos are left alone.
+    pub(crate) fn filter_new_videos(mut self, filter: &Filter) -> Self {
+        let (kept, rejected): (Vec<VideoBuilder>, Vec<VideoBuilder>) = self
+            .new_videos
+            .into_iter()
+            .partition(|video| filter.keep(video.video(), false));
+
+        self.new_videos = kept;
+        let old_videos = &self.old_videos;
+        self.channel.rejected_links.extend(
+            rejected
+                .iter()
+                .map(VideoBuilder::video)
+                .filter(|video| !old_videos.contains(video))
+                .map(|video| video.link().clone()),
+        );
+        self
+    }
+
     pub fn build(mut self) -> Channel {
-        // set already known videos
+        // set already known videos; streams that are still running are part of the new videos
         let mut videos = self.old_videos;
+        videos.retain(|video| !video.is_live());
 
         // iterate over new videos and add unknown
         for video_factory in self.new_videos.into_iter() {
             let video = video_factory.build();
             let position = videos.iter().position(|v| v == &video);
             if let Some(i) = position {
-                let v = videos.get_mut(i).unwrap();
-                v.title = video.title;
-                v.dearrow_title = video.dearrow_title;
+                videos.get_mut(i).unwrap().update_from(video);
             } else {
                 videos.push(video);
             }
@@ -84,30 +106,68 @@ impl ChannelBuilder {
     }
 }
 
-/* #[cfg(test)]
- * mod tests {
- *     use super::*;
- *     use rand::{distributions::Alphanumeric, Rng};
- *
- *     fn random_string() -> String {
- *         rand::thread_rng()
- *             .sample_iter(&Alphanumeric)
- *             .take(9)
- *             .map(char::from)
- *             .collect()
- *     }
- *
- *     impl ChannelFactory {
- *         pub fn test() -> Self {
- *             let mut cf = ChannelFactory::create();
- *             cf.set_name(String::new());
- *             cf.set_id(random_string());
- *             cf.set_tag(String::new());
- *             cf.add_new_videos(Vec::new());
- *             cf.set_old_videos(Vec::new());
- *             cf.set_sorting(SortingMethod::Date);
- *
- *             cf
- *         }
- *     }
- * } */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{
+        data::{
+            channel_list::ChannelList,
+            filter::{FilterRule, FilterRules},
+        },
+        SortingMethodChannels, StoredVideosFilter,
+    };
+
+    fn video(id: &str, title: &str) -> VideoBuilder {
+        let mut video = VideoBuilder::create();
+        video.set_id(id.to_string());
+        video.set_title(title.to_string());
+        video.set_link(format!("https://example.com/{}", id));
+        video.set_pub_date_from_feed("2024-01-01T10:00:00+00:00");
+        video
+    }
+
+    fn links(videos: &[Video]) -> Vec<&str> {
+        let mut links: Vec<&str> = videos.iter().map(|video| video.link().as_str()).collect();
+        links.sort();
+        links
+    }
+
+    #[test]
+    fn blocked_dearrow_title_takes_back_sent_video() {
+        let rules = FilterRules {
+            block: vec![FilterRule {
+                dearrow_title: Some("(?i)shocking".to_string()),
+                ..FilterRule::default()
+            }],
+            ..FilterRules::default()
+        };
+        let filter = Filter::new(&[&rules], None).unwrap();
+
+        for stored in [StoredVideosFilter::Hide, StoredVideosFilter::Delete] {
+            // `c` was stored by an earlier fetch and is listed again
+            let mut builder = ChannelBuilder::default()
+                .with_id("channel".to_string())
+                .with_old_videos(vec![video("c", "Stored").build()]);
+            builder.new_videos = vec![video("a", "Calm title"), video("b", "Calm title too"), video("c", "Stored")];
+
+            // first update, dearrow titles are not known yet
+            let mut channel_list = ChannelList::default();
+            let sort = SortingMethodChannels::AlphaNumeric;
+            channel_list.update_channel(builder.clone().build(), sort, stored);
+            assert_eq!(channel_list.get_unfiltered_by_id("channel").unwrap().len(), 3);
+
+            // second update, as after `use_dearrow`
+            builder.new_videos[1].set_dearrow_title(Some("SHOCKING reveal".to_string()));
+            builder.new_videos[2].set_dearrow_title(Some("Shocking, but stored".to_string()));
+            let channel = builder.filter_new_videos(&filter).build();
+            channel_list.update_channel(channel, sort, stored);
+
+            let channel = channel_list.get_unfiltered_by_id("channel").unwrap();
+            assert_eq!(links(&channel.videos), ["https://example.com/a", "https://example.com/c"]);
+            match stored {
+                StoredVideosFilter::Delete => assert!(channel.hidden_videos.is_empty()),
+                _ => assert_eq!(links(&channel.hidden_videos), ["https://example.com/b"]),
+            }
+        }
+    }
+}
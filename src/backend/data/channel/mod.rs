@@ -19,8 +19,12 @@ pub struct Channel {
     pub(super) name: String,
     pub(super) id: String,
     pub(crate) videos: Vec<Video>,
+    /// stored videos that do not pass the current filters
+    #[serde(default)]
+    pub(crate) hidden_videos: Vec<Video>,
 
-    #[serde(skip_deserializing)]
+    /// taken from the subscriptions, not stored since DB version 1
+    #[serde(skip)]
     pub sorting_method: SortingMethodVideos,
     #[serde(skip)]
     pub(super) tag: String,
@@ -29,6 +33,10 @@ pub struct Channel {
 
     #[serde(skip)]
     pub fetch_state: FetchState,
+    /// links of fetched videos that were only rejected once their dearrow title was known;
+    /// an earlier update of the same fetch may already have added them
+    #[serde(skip)]
+    pub(crate) rejected_links: Vec<String>,
 }
 
 #[allow(clippy::unnecessary_unwrap)]
@@ -37,6 +45,18 @@ impl Channel {
         ChannelBuilder::default()
     }
 
+    /// Channel as read from a storage backend; tag and sorting are set from the subscriptions later
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn from_stored(id: String, name: String, videos: Vec<Video>, hidden_videos: Vec<Video>) -> Self {
+        Self {
+            name,
+            id,
+            videos,
+            hidden_videos,
+            ..Self::default()
+        }
+    }
+
     pub fn next(&mut self) {
         let state = &self.list_state;
         let index = match state.selected() {
@@ -84,6 +104,10 @@ impl Channel {
         self.sorting_method = url_file_channel.sorting_method();
     }
 
+    pub fn is_live(&self) -> bool {
+        self.videos.iter().any(|video| video.is_live())
+    }
+
     pub fn id(&self) -> &String {
         &self.id
     }
@@ -135,13 +159,23 @@ impl Channel {
     }
 
     // add only missing videos, always uses title from new video
+    // streams that are not running anymore are removed
     pub fn merge_videos(&mut self, other_videos: Vec<Video>) {
+        self.videos
+            .retain(|video| !video.is_live() || other_videos.contains(video));
+
         for video in other_videos.into_iter() {
+            // new videos passed the current filters, so hidden ones are shown again
+            if let Some(i) = self.hidden_videos.iter().position(|v| v == &video) {
+                let mut hidden = self.hidden_videos.remove(i);
+                hidden.update_from(video);
+                self.push(hidden);
+                continue;
+            }
+
             let position = self.videos.iter().position(|v| v == &video);
             if let Some(i) = position {
-                let v = self.get_mut(i).unwrap();
-                v.title = video.title;
-                v.dearrow_title = video.dearrow_title;
+                self.get_mut(i).unwrap().update_from(video);
             } else {
                 self.push(video);
             }
@@ -150,6 +184,89 @@ impl Channel {
         self.sort();
     }
 
+    /// Hide (or delete) the videos with `links`, favourites are kept
+    pub(crate) fn hide_rejected(&mut self, links: &[String], delete: bool) {
+        if links.is_empty() {
+            return;
+        }
+        let (videos, rejected): (Vec<Video>, Vec<Video>) = self
+            .videos
+            .drain(..)
+            .partition(|video| video.is_fav() || !links.contains(video.link()));
+
+        self.videos = videos;
+        if !delete {
+            self.hidden_videos.extend(rejected);
+        }
+        self.select(self.selected());
+    }
+
+    /// Hide (or delete) all stored videos that `keep` rejects and show hidden videos
+    /// that it accepts again. Favourites are always kept.
+    pub(crate) fn refilter(&mut self, keep: impl Fn(&Video) -> bool, delete: bool) {
+        let (videos, hidden): (Vec<Video>, Vec<Video>) = self
+            .videos
+            .drain(..)
+            .chain(self.hidden_videos.drain(..))
+            .partition(|video| video.is_fav() || keep(video));
+
+        self.videos = videos;
+        self.hidden_videos = if delete { Vec::new() } else { hidden };
+        self.select(None);
+        self.sort();
+    }
+
+    /// Remove marked videos older than `max_seen_age` days and the oldest videos beyond
+    /// `max_videos`. Running streams are never removed, favourites only if `keep_favourites`
+    /// is false. Returns the removed videos.
+    pub(crate) fn prune(
+        &mut self,
+        max_videos: Option<usize>,
+        max_seen_age: Option<i64>,
+        keep_favourites: bool,
+    ) -> Vec<Video> {
+        let prunable = |video: &Video| !(video.is_live() || keep_favourites && video.is_fav());
+        let too_old = |video: &Video| {
+            max_seen_age.is_some_and(|days| {
+                video.marked() && video.age_in_days().is_some_and(|age| age > days)
+            })
+        };
+
+        let mut pruned = Vec::new();
+        for list in [&mut self.videos, &mut self.hidden_videos] {
+            let (old, kept): (Vec<Video>, Vec<Video>) = list
+                .drain(..)
+                .partition(|video| prunable(video) && too_old(video));
+            *list = kept;
+            pruned.extend(old);
+        }
+
+        if let Some(max_videos) = max_videos {
+            // newest first, the oldest prunable ones are removed until the limit is met
+            self.videos.sort_by(|a, b| b.pub_date().cmp(a.pub_date()));
+            let mut excess = self.videos.len().saturating_sub(max_videos);
+            let mut kept = Vec::with_capacity(self.videos.len());
+            for video in self.videos.drain(..).rev() {
+                if excess > 0 && prunable(&video) {
+                    excess -= 1;
+                    pruned.push(video);
+                } else {
+                    kept.push(video);
+                }
+            }
+            kept.reverse();
+            self.videos = kept;
+        }
+
+        // the channel might be open while it is updated
+        if self.list_state.selected().is_some_and(|i| i >= self.videos.len()) {
+            self.select(self.videos.len().checked_sub(1));
+        }
+        self.sort();
+
+        pruned
+    }
+
     #[allow(dead_code)]
     pub fn contains(&self, video: &Video) -> bool {
         self.videos.contains(video)
@@ -219,7 +336,7 @@ impl ToTuiListItem for Channel {
 
         let video_count = format!("{}/{}", num_marked, &self.videos.len());
 
-        let fetch_state = format!(" - {:?}", self.fetch_state);
+        let fetch_state = format!(" - {}", self.fetch_state);
 
         let new = if has_new {
             " * ".to_string()
@@ -228,8 +345,11 @@ impl ToTuiListItem for Channel {
         };
         let name = self.name.to_string();
 
+        let live = if self.is_live() { " LIVE" } else { "" };
+
         let spacer = String::from(" - ");
 
+        let red = Style::default().fg(Color::Red);
         let light_green = Style::default().fg(Color::LightGreen);
         let yellow = Style::default().fg(Color::Yellow);
         let blue = Style::default().fg(Color::Blue);
@@ -242,6 +362,7 @@ impl ToTuiListItem for Channel {
             ListItem::new(Line::from(vec![
                 Span::styled(new, light_green),
                 Span::styled(name, yellow),
+                Span::styled(live, red),
                 Span::styled(tag, blue),
                 Span::styled(spacer, gray),
                 Span::styled(video_count, gray),
@@ -251,6 +372,7 @@ impl ToTuiListItem for Channel {
             ListItem::new(Line::from(vec![
                 Span::styled(new, gray),
                 Span::styled(name, gray),
+                Span::styled(live, red),
                 Span::styled(tag, gray),
                 Span::styled(spacer, gray),
                 Span::styled(video_count, gray),
@@ -282,3 +404,93 @@ impl ToTuiListItem for Channel {
  *         }
  *     }
  * } */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::data::video::builder::VideoBuilder;
+
+    fn video(title: &str) -> Video {
+        let mut builder = VideoBuilder::create();
+        builder.set_title(title.to_string());
+        builder.set_link(format!("https://example.com/{}", title));
+        builder.set_pub_date_from_feed("2024-01-01T00:00:00+00:00");
+        builder.build()
+    }
+
+    fn titles(videos: &[Video]) -> Vec<&str> {
+        videos.iter().map(|video| video.title().as_str()).collect()
+    }
+
+    #[test]
+    fn refilter_hides_and_restores() {
+        let mut channel = Channel::builder()
+            .with_old_videos(vec![video("Podcast"), video("Vlog")])
+            .build();
+
+        channel.refilter(|video| video.title() == "Podcast", false);
+        assert_eq!(titles(&channel.videos), ["Podcast"]);
+        assert_eq!(titles(&channel.hidden_videos), ["Vlog"]);
+
+        // loosened rules bring hidden videos back
+        channel.refilter(|_| true, false);
+        assert_eq!(channel.videos.len(), 2);
+        assert!(channel.hidden_videos.is_empty());
+
+        channel.refilter(|video| video.title() == "Podcast", true);
+        assert_eq!(titles(&channel.videos), ["Podcast"]);
+        assert!(channel.hidden_videos.is_empty());
+    }
+
+    #[test]
+    fn merge_shows_hidden_videos_again() {
+        let mut channel = Channel::builder()
+            .with_old_videos(vec![video("Podcast"), video("Vlog")])
+            .build();
+        channel.refilter(|video| video.title() == "Podcast", false);
+
+        // a fetched video already passed the filters of the fetch
+        channel.merge_videos(vec![video("Vlog")]);
+        assert_eq!(channel.videos.len(), 2);
+        assert!(channel.hidden_videos.is_empty());
+    }
+
+    #[test]
+    fn prune_keeps_favourites_and_newest() {
+        let dated = |title: &str, days: i64, marked: bool| {
+            let mut builder = VideoBuilder::create();
+            builder.set_title(title.to_string());
+            builder.set_link(format!("https://example.com/{}", title));
+            let date = chrono::Local::now() - chrono::Duration::days(days);
+            builder.set_pub_date_from_feed(&date.to_rfc3339());
+            let mut video = builder.build();
+            video.mark(marked);
+            video
+        };
+
+        let mut fav = dated("Fav", 400, true);
+        fav.set_fav(true);
+        let mut channel = Channel::builder()
+            .with_old_videos(vec![
+                dated("New", 1, false),
+                dated("Week", 7, false),
+                dated("Seen", 100, true),
+                dated("Unseen", 100, false),
+                fav,
+            ])
+            .build();
+
+        let pruned = channel.prune(None, Some(30), true);
+        assert_eq!(titles(&pruned), ["Seen"]);
+        assert_eq!(channel.len(), 4);
+
+        // the oldest video is a favourite, so the next older ones are removed instead
+        let pruned = channel.prune(Some(2), None, true);
+        assert_eq!(titles(&pruned), ["Unseen", "Week"]);
+        channel.videos.sort_by_key(|video| video.title().clone());
+        assert_eq!(titles(&channel.videos), ["Fav", "New"]);
+
+        let pruned = channel.prune(Some(1), None, false);
+        assert_eq!(titles(&pruned), ["Fav"]);
+    }
+}
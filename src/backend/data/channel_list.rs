@@ -1,20 +1,26 @@
 #![allow(unused)]
 
 use crate::backend::{
-    data::channel::Channel,
+    data::{channel::Channel, filter::Filter as VideoFilter, migration::{migrate, DB_VERSION}, video::Video},
+    io::config::Config,
     io::subscriptions::{SubscriptionItem, Subscriptions},
-    io::{read_config, FileType::DbFile},
+    io::{read_config, write_config, FileType::DbFile},
     Error::ParseDB,
     Filter::{self, *},
-    SortingMethodChannels,
+    SortingMethodChannels, Storage, StoredVideosFilter,
     Result, ToTuiListItem,
 };
+#[cfg(feature = "sqlite")]
+use crate::backend::io::sqlite::Database;
 use serde::{Deserialize, Serialize};
 use std::cmp::min;
 use tui::widgets::{ListItem, ListState};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ChannelList {
+    /// format of the stored file, see `migration`
+    #[serde(default)]
+    version: u64,
     channels: Vec<Channel>,
 
     #[serde(skip)]
@@ -26,6 +32,7 @@ pub(crate) struct ChannelList {
 impl Default for ChannelList {
     fn default() -> Self {
         Self {
+            version: DB_VERSION,
             channels: vec![Channel::default()],
             list_state: ListState::default(),
             filter: Filter::NoFilter,
@@ -35,18 +42,83 @@ impl Default for ChannelList {
 
 #[allow(clippy::unnecessary_unwrap)]
 impl ChannelList {
-    pub(crate) fn load() -> Result<Self> {
-        let db_file = read_config(DbFile);
+    pub(crate) fn load(config: &Config) -> Result<Self> {
+        let mut channel_list = match config.storage {
+            #[cfg(feature = "sqlite")]
+            Storage::Sqlite => Self {
+                channels: Database::with(|db| db.load_channels())?,
+                ..Self::default()
+            },
+            _ => Self::from_json(&read_config(DbFile))?,
+        };
+
+        channel_list.apply_url_file_changes(config);
+        Ok(channel_list)
+    }
+
+    /// Parse a stored `db.json`, upgrading files of older versions
+    pub(crate) fn from_json(json: &str) -> Result<Self> {
+        let mut db: serde_json::Value = serde_json::from_str(json).map_err(ParseDB)?;
+        migrate(&mut db)?;
+        serde_json::from_value(db).map_err(ParseDB)
+    }
+
+    /// Store all channels
+    pub(crate) fn save(&self, storage: Storage) -> Result<()> {
+        match storage {
+            #[cfg(feature = "sqlite")]
+            Storage::Sqlite => Database::with(|db| db.save_channels(&self.channels)),
+            _ => {
+                let string = serde_json::to_string(self).unwrap();
+                write_config(DbFile, &string)
+            }
+        }
+    }
 
-        match serde_json::from_str::<Self>(&db_file) {
-            Ok(mut channel_list) => {
-                channel_list.apply_url_file_changes();
-                Ok(channel_list)
+    /// Store the channel `id`, e.g. after it was fetched. With json, everything is written.
+    pub(crate) fn save_channel(&self, id: &str, storage: Storage) -> Result<()> {
+        match storage {
+            #[cfg(feature = "sqlite")]
+            Storage::Sqlite => match self.channels.iter().position(|c| c.id() == id) {
+                Some(position) => Database::with(|db| db.save_channel(position, &self.channels[position])),
+                None => Ok(()),
+            },
+            _ => self.save(storage),
+        }
+    }
+
+    /// Store a single changed video of channel `id`, e.g. after a mark
+    pub(crate) fn save_video(&self, id: &str, video: &Video, storage: Storage) -> Result<()> {
+        match storage {
+            #[cfg(feature = "sqlite")]
+            Storage::Sqlite => {
+                if Database::with(|db| db.update_video(id, video))? {
+                    Ok(())
+                } else {
+                    self.save_channel(id, storage)
+                }
             }
-            Err(error) => Err(ParseDB(error)),
+            _ => self.save(storage),
         }
     }
 
+    /// Shown and hidden videos of all channels
+    pub(crate) fn all_videos(&self) -> impl Iterator<Item = &Video> {
+        self.channels
+            .iter()
+            .flat_map(|channel| channel.videos.iter().chain(channel.hidden_videos.iter()))
+    }
+
+    pub(crate) fn all_videos_mut(&mut self) -> impl Iterator<Item = &mut Video> {
+        self.channels
+            .iter_mut()
+            .flat_map(|channel| channel.videos.iter_mut().chain(channel.hidden_videos.iter_mut()))
+    }
+
+    pub(crate) fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
     pub(crate) fn next(&mut self) {
         let state = &self.list_state;
         let index = match state.selected() {
@@ -203,8 +275,23 @@ impl ChannelList {
         }
     }
 
+    /// Apply the retention settings to the channel `id` and return the removed videos.
+    /// Channels hidden by the filter are pruned as well, they are the ones full of seen videos.
+    pub(crate) fn prune_channel(&mut self, id: &str, config: &Config) -> Vec<Video> {
+        match self.get_unfiltered_mut_by_id(id) {
+            Some(channel) => channel.prune(
+                config.max_videos_per_channel,
+                config.max_seen_age,
+                config.keep_favourites,
+            ),
+            None => Vec::new(),
+        }
+    }
+
     /// Add new videos to already known channel
-    pub fn update_channel(&mut self, updated_channel: Channel, sort: SortingMethodChannels) {
+    /// Videos that were only rejected by the last update of a fetch are hidden or deleted
+    /// like stored videos after a filter change
+    pub fn update_channel(&mut self, updated_channel: Channel, sort: SortingMethodChannels, stored: StoredVideosFilter) {
         let filter = self.get_filter();
         self.set_filter(Filter::NoFilter);
 
@@ -212,6 +299,7 @@ impl ChannelList {
 
         if let Some(channel) = old_channel {
             channel.merge_videos(updated_channel.videos); // merge videos of new and old version
+            channel.hide_rejected(&updated_channel.rejected_links, stored == StoredVideosFilter::Delete);
         } else {
             self.push(updated_channel);
         }
@@ -246,6 +334,8 @@ impl ChannelList {
             if let Some(mut channel) = self.get_mut_by_id(&custom_channel.id()) {
                 channel.videos
                     .retain(|video| urls.contains(video.origin_url()));
+                channel.hidden_videos
+                    .retain(|video| urls.contains(video.origin_url()));
             }
         }
     }
@@ -266,10 +356,47 @@ impl ChannelList {
         }
     }
 
-    pub(crate) fn apply_url_file_changes(&mut self) {
+    /// Apply the current filters to the stored videos, so changed rules affect them as well
+    fn filter_stored_videos(&mut self, subs: &Subscriptions, config: &Config) {
+        if config.filter_stored_videos == StoredVideosFilter::Off {
+            return;
+        }
+        let delete = config.filter_stored_videos == StoredVideosFilter::Delete;
+
+        let items = subs
+            .channels
+            .iter()
+            .map(|item| item as &dyn SubscriptionItem)
+            .chain(subs.custom_channels.iter().map(|item| item as &dyn SubscriptionItem));
+
+        for item in items {
+            let channel = match self.get_unfiltered_mut_by_id(&item.id()) {
+                Some(channel) => channel,
+                None => continue,
+            };
+
+            let filter = match VideoFilter::new(&[&config.filter, item.filter()], item.block_regex().as_deref()) {
+                Ok(filter) => filter,
+                Err(_) => continue,
+            };
+            let hide_shorts = item.hide_shorts().unwrap_or(config.hide_shorts);
+            let hide_upcoming = item.hide_upcoming().unwrap_or(config.hide_upcoming);
+
+            channel.refilter(
+                |video| {
+                    filter.keep(video, false)
+                        && !video.kind().is_hidden(hide_shorts, hide_upcoming)
+                },
+                delete,
+            );
+        }
+    }
+
+    pub(crate) fn apply_url_file_changes(&mut self, config: &Config) {
         if let Ok(subs) = Subscriptions::read() {
             self.remove_old(&subs);
             self.update_channels_from_url_file(&subs);
+            self.filter_stored_videos(&subs, config);
         }
     }
 
@@ -347,3 +474,32 @@ impl PartialEq<ChannelList> for ChannelList {
  *         assert_eq!(channel_list.get(0).unwrap().id(), &String::from("channel_2"));
  *     }
  * } */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_channel_hidden_by_filter() {
+        let mut channel_list = ChannelList::from_json(
+            r#"{"version": 2, "channels": [{"name": "Seen", "id": "seen", "videos": [
+                {"title": "Old", "link": "https://example.com/old", "origin_url": "", "origin_channel_name": "Seen",
+                 "marked": true, "pubDate": "2020-01-01T10:00:00+00:00"}
+            ]}]}"#,
+        )
+        .unwrap();
+
+        // without unseen videos the channel is not in the filtered list
+        channel_list.set_filter(OnlyNew);
+        assert!(channel_list.get_by_id("seen").is_none());
+
+        let config = Config {
+            max_seen_age: Some(30),
+            ..Config::default()
+        };
+        let pruned = channel_list.prune_channel("seen", &config);
+
+        assert_eq!(pruned.len(), 1);
+        assert!(channel_list.get_unfiltered_by_id("seen").unwrap().videos.is_empty());
+    }
+}
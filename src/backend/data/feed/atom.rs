@@ -1,5 +1,4 @@
 use serde::Deserialize;
-/* use crate::internal; */
 
 // Deserialize structs
 #[derive(Debug, Deserialize)]
@@ -7,51 +6,117 @@ pub struct Feed {
     // like channel in rss / internal
     #[serde(rename = "title")]
     pub name: String,
-    #[serde(rename = "channelId")]
-    pub channel_id: String,
-    #[serde(rename = "entry")]
+    #[serde(rename = "entry", default)]
     pub videos: Vec<Video>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Video {
     pub title: String,
-    #[serde(rename = "videoId")]
     pub id: String,
-    #[serde(rename = "published")]
-    pub pub_date: String,
-}
-
-/* #[allow(dead_code)]
- * impl Feed {
- *     pub fn to_internal_channel(self, original_link: &String) -> internal::Channel {
- *         let name = self.name;
- *         let link = format!("https://www.youtube.com/channel/{}", self.channel_id);
- *         let mut videos: Vec<internal::Video> = self.videos.into_iter().map(|v| v.to_internal_video(original_link)).collect();
- *
- *         let mut channel = internal::Channel::new();
- *         channel.name = name;
- *         channel.id = link;
- *         // channel.videos = videos;
- *         channel.append(&mut videos);
- *
- *         channel
- *     }
- * } */
-
-/* #[allow(dead_code)]
- * impl Video {
- *     fn to_internal_video(self, channel_link: &String) -> internal::Video {
- *         let title = self.title;
- *         let link = format!("https://www.youtube.com/watch?v={}", self.id);
- *         let pub_date = self.pub_date;
- *
- *         internal::Video {
- *             title,
- *             link,
- *             pub_date,
- *             channel_link: channel_link.clone(),
- *             ..internal::Video::new()
- *         }
- *     }
- * } */
+    /// only present in YouTube feeds (`yt:videoId`)
+    #[serde(rename = "videoId")]
+    pub video_id: Option<String>,
+    #[serde(rename = "link", default)]
+    pub links: Vec<Link>,
+    pub published: Option<String>,
+    pub updated: Option<String>,
+    pub summary: Option<String>,
+    /// `media:group` as in YouTube feeds
+    #[serde(rename = "group")]
+    pub media: Option<MediaGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MediaGroup {
+    pub description: Option<String>,
+    pub thumbnail: Option<Thumbnail>,
+    pub community: Option<Community>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Thumbnail {
+    #[serde(rename = "@url")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Community {
+    #[serde(rename = "starRating")]
+    pub star_rating: Option<StarRating>,
+    pub statistics: Option<Statistics>,
+}
+
+/// attributes are read as text, a malformed value only drops the value and not the feed
+#[derive(Debug, Deserialize)]
+pub struct StarRating {
+    #[serde(rename = "@average")]
+    pub average: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Statistics {
+    #[serde(rename = "@views")]
+    pub views: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Link {
+    #[serde(rename = "@href")]
+    pub href: String,
+    #[serde(rename = "@rel")]
+    pub rel: Option<String>,
+}
+
+impl Video {
+    /// Link to the page of the entry. YouTube links are built from the video id,
+    /// otherwise the `alternate` link is used, falling back to the entry id if it is an url.
+    pub fn link(&self) -> Option<String> {
+        if let Some(video_id) = &self.video_id {
+            return Some(format!("https://www.youtube.com/watch?v={}", video_id));
+        }
+
+        // a link without rel is an alternate link
+        let alternate = self
+            .links
+            .iter()
+            .find(|link| matches!(link.rel.as_deref(), None | Some("alternate")));
+
+        alternate
+            .or_else(|| self.links.first())
+            .map(|link| link.href.clone())
+            .or_else(|| self.id.starts_with("http").then(|| self.id.clone()))
+    }
+
+    pub fn description(&self) -> Option<String> {
+        let media = self.media.as_ref().and_then(|m| m.description.clone());
+        media.or_else(|| self.summary.clone()).filter(|d| !d.is_empty())
+    }
+
+    pub fn thumbnail(&self) -> Option<String> {
+        self.media.as_ref()?.thumbnail.as_ref()?.url.clone().filter(|url| !url.is_empty())
+    }
+
+    /// youtube links shorts as `/shorts/<id>` instead of `/watch?v=<id>`
+    pub fn is_short(&self) -> bool {
+        self.links.iter().any(|link| link.href.contains("/shorts/"))
+    }
+
+    pub fn views(&self) -> Option<u64> {
+        let views = self.media.as_ref()?.community.as_ref()?.statistics.as_ref()?.views.as_ref()?;
+        views.trim().parse().ok()
+    }
+
+    pub fn rating(&self) -> Option<f32> {
+        let average = self.media.as_ref()?.community.as_ref()?.star_rating.as_ref()?.average.as_ref()?;
+        average.trim().parse().ok()
+    }
+
+    /// Publishing date, or the last update if the feed does not tell
+    pub fn date(&self) -> &str {
+        self.published
+            .as_deref()
+            .or(self.updated.as_deref())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+
+/// Time zone abbreviations seen in feeds that RFC 2822 does not know about
+const ZONES: &[(&str, &str)] = &[
+    ("UTC", "+0000"),
+    ("Z", "+0000"),
+    ("WET", "+0000"),
+    ("BST", "+0100"),
+    ("CET", "+0100"),
+    ("WEST", "+0100"),
+    ("CEST", "+0200"),
+    ("EET", "+0200"),
+    ("EEST", "+0300"),
+    ("MSK", "+0300"),
+    ("IST", "+0530"),
+    ("JST", "+0900"),
+    ("AEST", "+1000"),
+    ("AEDT", "+1100"),
+];
+
+/// RFC 3339 like formats with an offset it does not allow, e.g. without colon
+const ZONED_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+];
+
+/// Formats without any zone information; these are taken as UTC
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Parse the publishing date of a feed entry.
+///
+/// Accepts RFC 2822 and RFC 3339 as well as the usual deviations from them:
+/// full or wrong weekday names, zone abbreviations and dates without any zone.
+pub(crate) fn parse_date(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc2822(text) {
+        return Some(date);
+    }
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date);
+    }
+
+    // rfc 2822 without the (optional) weekday, which is sometimes spelled out or wrong
+    let without_weekday = match text.split_once(',') {
+        Some((weekday, rest)) if weekday.chars().all(char::is_alphabetic) => rest.trim(),
+        _ => text,
+    };
+
+    if let Ok(date) = DateTime::parse_from_rfc2822(&replace_zone(without_weekday)) {
+        return Some(date);
+    }
+
+    for format in ZONED_FORMATS {
+        if let Ok(date) = DateTime::parse_from_str(text, format) {
+            return Some(date);
+        }
+    }
+
+    let utc = FixedOffset::east_opt(0)?;
+
+    for format in NAIVE_FORMATS {
+        if let Ok(date) = NaiveDateTime::parse_from_str(text, format) {
+            return date.and_local_timezone(utc).single();
+        }
+    }
+
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+    date.and_hms_opt(0, 0, 0)?.and_local_timezone(utc).single()
+}
+
+/// replace a trailing zone abbreviation by its numeric offset
+fn replace_zone(text: &str) -> String {
+    if let Some((rest, zone)) = text.rsplit_once(' ') {
+        let zone = zone.to_uppercase();
+        if let Some((_, offset)) = ZONES.iter().find(|(name, _)| *name == zone) {
+            return format!("{} {}", rest, offset);
+        }
+    }
+
+    text.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let cases = [
+            // rfc 2822 with the zones it knows
+            ("Tue, 02 Jan 2024 10:00:00 +0100", "2024-01-02T10:00:00+01:00"),
+            ("Tue, 02 Jan 2024 10:00:00 GMT", "2024-01-02T10:00:00+00:00"),
+            ("Tue, 02 Jan 2024 10:00:00 UT", "2024-01-02T10:00:00+00:00"),
+            ("Tue, 02 Jan 2024 10:00:00 EST", "2024-01-02T10:00:00-05:00"),
+            ("Tue, 02 Jan 2024 10:00:00 PDT", "2024-01-02T10:00:00-07:00"),
+            ("02 Jan 2024 10:00:00 +0000", "2024-01-02T10:00:00+00:00"),
+            // zone abbreviations and weekdays rfc 2822 does not know
+            ("Tue, 02 Jan 2024 10:00:00 CEST", "2024-01-02T10:00:00+02:00"),
+            ("Tue, 02 Jan 2024 10:00:00 utc", "2024-01-02T10:00:00+00:00"),
+            ("Tuesday, 02 Jan 2024 10:00:00 GMT", "2024-01-02T10:00:00+00:00"),
+            ("Wed, 02 Jan 2024 10:00:00 +0000", "2024-01-02T10:00:00+00:00"),
+            // rfc 3339
+            ("2024-01-02T10:00:00+01:00", "2024-01-02T10:00:00+01:00"),
+            ("2024-01-02T10:00:00.123Z", "2024-01-02T10:00:00.123+00:00"),
+            ("2024-01-02T10:00:00+0100", "2024-01-02T10:00:00+01:00"),
+            ("2024-01-02 10:00:00 +0100", "2024-01-02T10:00:00+01:00"),
+            // without zone, taken as utc
+            ("2024-01-02T10:00:00", "2024-01-02T10:00:00+00:00"),
+            ("2024-01-02 10:00:00.5", "2024-01-02T10:00:00.500+00:00"),
+            ("2024-01-02T10:00", "2024-01-02T10:00:00+00:00"),
+            ("2024-01-02 10:00", "2024-01-02T10:00:00+00:00"),
+            ("2024-01-02", "2024-01-02T00:00:00+00:00"),
+            ("  2024-01-02  ", "2024-01-02T00:00:00+00:00"),
+        ];
+
+        for (text, expected) in cases {
+            let date = parse_date(text).unwrap_or_else(|| panic!("{:?} was not parsed", text));
+            assert_eq!(date, DateTime::parse_from_rfc3339(expected).unwrap(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parse_garbage() {
+        for text in ["", "yesterday", "02/01/2024", "2024-13-02", "Tue, 32 Jan 2024 10:00:00 GMT", "Tue, 02 Jan 2024 10:00:00 XYZ"] {
+            assert_eq!(parse_date(text), None, "{:?}", text);
+        }
+    }
+}
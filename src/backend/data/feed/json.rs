@@ -0,0 +1,78 @@
+use serde::Deserialize;
+
+// Deserialize structs for JSON Feed (https://www.jsonfeed.org/version/1.1/)
+#[derive(Debug, Deserialize)]
+pub struct Feed {
+    pub version: String,
+    #[serde(rename = "title")]
+    pub name: String,
+    #[serde(rename = "items", default)]
+    pub videos: Vec<Video>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Video {
+    pub id: serde_json::Value, // should be a string, but numbers are common
+    pub url: Option<String>,
+    pub external_url: Option<String>,
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub content_text: Option<String>,
+    pub date_published: Option<String>,
+    pub date_modified: Option<String>,
+    pub image: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Attachment {
+    pub url: String,
+    pub title: Option<String>,
+    pub duration_in_seconds: Option<f64>,
+}
+
+impl Feed {
+    /// Only JSON Feed documents declare this version url
+    pub fn is_json_feed(&self) -> bool {
+        self.version.starts_with("https://jsonfeed.org/version/")
+    }
+}
+
+impl Video {
+    /// Page of the item; attachments and the id are used if no url is given
+    pub fn link(&self) -> Option<String> {
+        self.url
+            .clone()
+            .or_else(|| self.external_url.clone())
+            .or_else(|| self.attachments.first().map(|a| a.url.clone()))
+            .or_else(|| match &self.id {
+                serde_json::Value::String(id) if id.starts_with("http") => Some(id.clone()),
+                _ => None,
+            })
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.content_text.clone().or_else(|| self.summary.clone())
+    }
+
+    pub fn duration(&self) -> Option<u64> {
+        let seconds = self.attachments.iter().find_map(|a| a.duration_in_seconds)?;
+        Some(seconds.round() as u64)
+    }
+
+    /// Titles are optional in JSON Feed; fall back to the summary or the text
+    pub fn display_title(&self) -> String {
+        let attachment_title = self.attachments.iter().find_map(|a| a.title.clone());
+
+        self.title
+            .clone()
+            .or(attachment_title)
+            .or_else(|| self.summary.clone())
+            .or_else(|| {
+                let text = self.content_text.as_ref()?;
+                Some(text.lines().next().unwrap_or_default().to_string())
+            })
+            .unwrap_or_default()
+    }
+}
@@ -1,34 +1,88 @@
 pub mod atom;
+pub(crate) mod date;
+pub mod json;
 pub mod rss;
 use quick_xml::de::from_str;
-use fancy_regex::Regex;
 
-use crate::backend::data::video::builder::VideoBuilder;
+use crate::backend::data::{
+    filter::Filter,
+    video::builder::VideoBuilder,
+};
+use std::fmt;
 
 #[derive(Default)]
 pub(crate) struct Feed {
     pub(crate) name: String,
     pub(crate) videos: Vec<VideoBuilder>,
+    /// problems that did not prevent parsing, shown to the user
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Reason why a single feed url could not be used
+#[derive(Clone, Debug)]
+pub(crate) enum FeedError {
+    Http(u16),
+    Network(String),
+    Parse(String),
+}
+
+impl fmt::Display for FeedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FeedError::Http(status) => write!(f, "HTTP status {}", status),
+            FeedError::Network(error) => write!(f, "Network error: {}", error),
+            FeedError::Parse(error) => write!(f, "Parse error: {}", error),
+        }
+    }
 }
 
 impl Feed {
-    pub fn parse_text(feed: String) -> Result<Self, String> {
-        // try to parse as atom
-        if let Ok(feed) = from_str::<atom::Feed>(&feed) {
-            return Ok(feed.into());
+    /// Parse as JSON Feed, atom or rss. JSON is recognized by the content type or by the
+    /// text starting with `{`. On failure the error of every tried parser is returned.
+    pub fn parse_text(feed: String, content_type: Option<&str>) -> Result<Self, String> {
+        let is_json = content_type.is_some_and(|t| t.contains("json"))
+            || feed.trim_start().starts_with('{');
+
+        if is_json {
+            return match serde_json::from_str::<json::Feed>(&feed) {
+                Ok(feed) if feed.is_json_feed() => Ok(feed.into()),
+                Ok(feed) => Err(format!("unknown json feed version ({})", feed.version)),
+                Err(error) => Err(format!("not json feed ({})", error)),
+            };
         }
 
+        // try to parse as atom
+        let atom_error = match from_str::<atom::Feed>(&feed) {
+            Ok(feed) => return Ok(feed.into()),
+            Err(error) => error,
+        };
+
         // try to parse as rss
-        if let Ok(feed) = from_str::<rss::Feed>(&feed) {
-            return Ok(feed.into());
-        }
+        let rss_error = match from_str::<rss::Feed>(&feed) {
+            Ok(feed) => return Ok(feed.into()),
+            Err(error) => error,
+        };
 
-        Err(String::from("Could not parse feed"))
+        Err(format!("not atom ({}), not rss ({})", atom_error, rss_error))
     }
 
-    pub fn filter_videos(&mut self, block_regex: Regex) {
+    /// Drop all videos that do not pass `filter`
+    pub(crate) fn filter_videos(&mut self, filter: &Filter, dearrow_pending: bool) {
         self.videos
-            .retain(|video| { ! block_regex.is_match(video.get_title()).unwrap() });
+            .retain(|video| filter.keep(video.video(), dearrow_pending));
+    }
+
+    /// Drop shorts and streams that did not start yet, if requested.
+    /// Returns whether upcoming videos were dropped.
+    pub fn filter_kinds(&mut self, hide_shorts: bool, hide_upcoming: bool) -> bool {
+        let len = self.videos.len();
+        self.videos
+            .retain(|video| !video.get_kind().is_hidden(false, hide_upcoming));
+        let dropped_upcoming = self.videos.len() != len;
+
+        self.videos
+            .retain(|video| !video.get_kind().is_hidden(hide_shorts, false));
+        dropped_upcoming
     }
 
     pub fn add_videos(&mut self, videos: Vec<VideoBuilder>) {
@@ -51,10 +105,11 @@ impl From<rss::Feed> for Feed {
         let name = feed.name;
         /* let id = feed.link; */
 
-        let videos = feed.videos.into_iter().map(VideoBuilder::from).collect();
+        let videos: Vec<VideoBuilder> = feed.videos.into_iter().map(VideoBuilder::from).collect();
+        let warnings = date_warnings(&videos);
 
         // Feed { name, id, videos }
-        Feed { name, videos }
+        Feed { name, videos, warnings }
     }
 }
 
@@ -63,9 +118,261 @@ impl From<atom::Feed> for Feed {
         let name = feed.name;
         /* let id = format!("https://www.youtube.com/channel/{}", feed.channel_id); */
 
-        let videos = feed.videos.into_iter().map(VideoBuilder::from).collect();
+        // entries without any link can not be opened
+        let videos: Vec<VideoBuilder> = feed
+            .videos
+            .into_iter()
+            .filter(|video| video.link().is_some())
+            .map(VideoBuilder::from)
+            .collect();
+        let warnings = date_warnings(&videos);
 
         // Feed { name, id, videos }
-        Feed { name, videos }
+        Feed { name, videos, warnings }
+    }
+}
+
+impl From<json::Feed> for Feed {
+    fn from(feed: json::Feed) -> Self {
+        let name = feed.name;
+
+        // items without any link can not be opened
+        let videos: Vec<VideoBuilder> = feed
+            .videos
+            .into_iter()
+            .filter(|video| video.link().is_some())
+            .map(VideoBuilder::from)
+            .collect();
+        let warnings = date_warnings(&videos);
+
+        Feed { name, videos, warnings }
+    }
+}
+
+fn date_warnings(videos: &[VideoBuilder]) -> Vec<String> {
+    videos
+        .iter()
+        .filter(|video| video.is_date_estimated())
+        .map(|video| format!("Unknown date format in '{}', using fetch time", video.get_title()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::data::video::VideoKind;
+
+    fn parse(fixture: &str, content_type: Option<&str>) -> Feed {
+        Feed::parse_text(fixture.to_string(), content_type).unwrap()
+    }
+
+    #[test]
+    fn parse_youtube_atom() {
+        let feed = parse(include_str!("fixtures/youtube.xml"), None);
+
+        assert_eq!(feed.name, "Tom Scott");
+        assert_eq!(feed.videos.len(), 2);
+        assert!(feed.warnings.is_empty());
+
+        assert_eq!(feed.videos[0].get_id().as_deref(), Some("7DKv5H5Frt0"));
+        let video = feed.videos[0].clone().build();
+        assert_eq!(video.title(), "This is the last video");
+        assert_eq!(video.link(), "https://www.youtube.com/watch?v=7DKv5H5Frt0");
+        assert_eq!(video.pub_date(), "2024-01-01T14:00:06+00:00");
+        assert_eq!(
+            video.description().unwrap(),
+            "Ten years of weekly videos.\nThank you for watching."
+        );
+        assert_eq!(
+            video.thumbnail().unwrap(),
+            "https://i1.ytimg.com/vi/7DKv5H5Frt0/hqdefault.jpg"
+        );
+        assert_eq!(video.views(), Some(3500000));
+        assert_eq!(video.rating(), Some(5.0));
+        assert_eq!(video.duration(), None);
+    }
+
+    #[test]
+    fn classify_youtube_kinds() {
+        let mut feed = parse(include_str!("fixtures/youtube_kinds.xml"), None);
+
+        let kinds: Vec<VideoKind> = feed.videos.iter().map(|v| v.get_kind()).collect();
+        assert_eq!(kinds, [VideoKind::Upcoming, VideoKind::Short, VideoKind::Regular]);
+
+        // shorts are still opened with the normal watch link
+        let video = feed.videos[1].clone().build();
+        assert_eq!(video.link(), "https://www.youtube.com/watch?v=Zx9Yw8Vu7Ts");
+
+        assert!(!feed.filter_kinds(true, false));
+        assert_eq!(feed.videos.len(), 2);
+        assert!(feed.filter_kinds(false, true));
+        assert_eq!(feed.videos.len(), 1);
+        assert_eq!(feed.videos[0].get_title(), "The last star in the universe");
+    }
+
+    #[test]
+    fn fresh_upload_without_views_is_regular() {
+        // scheduled for the future in the fixture; a fresh upload has a past date and no views yet
+        let fixture = include_str!("fixtures/youtube_kinds.xml")
+            .replacen("2099-02-01T10:00:00+00:00", "2024-02-01T10:00:00+00:00", 1);
+        let feed = parse(&fixture, None);
+
+        assert_eq!(feed.videos[0].video().views(), Some(0));
+        assert_eq!(feed.videos[0].get_kind(), VideoKind::Regular);
+    }
+
+    #[test]
+    fn parse_generic_atom() {
+        let feed = parse(include_str!("fixtures/atom.xml"), None);
+
+        assert_eq!(feed.name, "Example Talks");
+        assert_eq!(feed.videos.len(), 2);
+        assert!(feed.warnings.is_empty());
+
+        // alternate link is preferred over the enclosure, no youtube id is set
+        assert!(feed.videos[0].get_id().is_none());
+        let video = feed.videos[0].clone().build();
+        assert_eq!(video.link(), "https://talks.example.org/talks/keynote");
+        assert_eq!(video.pub_date(), "2024-03-10T18:30:02+00:00");
+
+        // without links, the id is used; published wins over updated
+        let video = feed.videos[1].clone().build();
+        assert_eq!(video.link(), "https://talks.example.org/talks/lightning");
+        assert_eq!(video.pub_date(), "2024-03-09T10:00:00+01:00");
+    }
+
+    #[test]
+    fn parse_rss() {
+        let feed = parse(include_str!("fixtures/rss.xml"), None);
+
+        assert_eq!(feed.name, "Example Streams");
+        assert_eq!(feed.videos.len(), 2);
+
+        let video = feed.videos[0].clone().build();
+        assert_eq!(video.title(), "Speedrun marathon");
+        assert_eq!(video.link(), "https://example.com/videos/123");
+        assert_eq!(video.pub_date(), "2021-03-02T18:55:52+00:00");
+        assert_eq!(video.description().unwrap(), "All the runs");
+        assert_eq!(video.thumbnail().unwrap(), "https://example.com/thumbs/123.jpg");
+        assert_eq!(video.duration(), Some(3723));
+        assert_eq!(video.duration_string().unwrap(), "1:02:03");
+
+        // unparsable dates do not fail the feed
+        assert!(feed.videos[1].is_date_estimated());
+        assert_eq!(feed.warnings.len(), 1);
+    }
+
+    #[test]
+    fn malformed_rss_metadata_is_skipped() {
+        let feed = parse(include_str!("fixtures/rss_malformed.xml"), None);
+        assert_eq!(feed.videos.len(), 2);
+
+        // fractions of a second are rounded, abbreviated view counts dropped
+        let video = feed.videos[0].clone().build();
+        assert_eq!(video.duration(), Some(755));
+        assert_eq!(video.views(), None);
+
+        // a thumbnail without url and an image without href do not fail the feed
+        let video = feed.videos[1].clone().build();
+        assert_eq!(video.thumbnail(), None);
+        assert_eq!(video.duration(), None);
+    }
+
+    #[test]
+    fn malformed_atom_metadata_is_skipped() {
+        let feed = parse(include_str!("fixtures/atom_malformed.xml"), None);
+        assert_eq!(feed.videos.len(), 1);
+
+        let video = feed.videos[0].clone().build();
+        assert_eq!(video.title(), "Odd statistics");
+        assert_eq!(video.thumbnail(), None);
+        assert_eq!(video.views(), None);
+        assert_eq!(video.rating(), None);
+    }
+
+    #[test]
+    fn parse_peertube() {
+        let feed = parse(include_str!("fixtures/peertube.xml"), None);
+
+        assert_eq!(feed.name, "Framasoft");
+        assert_eq!(feed.videos.len(), 2);
+        assert!(feed.warnings.is_empty());
+
+        let video = feed.videos[0].clone().build();
+        assert_eq!(video.title(), "PeerTube v6 is out");
+        assert_eq!(video.link(), "https://framatube.org/w/9c9de5e8-0a1e-484a-b099-e80766180a6d");
+        assert_eq!(video.pub_date(), "2024-01-10T09:00:00+00:00");
+        // the plain media:description is preferred over the html one
+        assert_eq!(
+            video.description().unwrap(),
+            "A new major version.\nRead the release notes & upgrade."
+        );
+        assert_eq!(
+            video.thumbnail().unwrap(),
+            "https://framatube.org/lazy-static/thumbnails/9c9de5e8.jpg"
+        );
+        assert_eq!(video.duration(), Some(754));
+        assert_eq!(video.views(), Some(4217));
+
+        let video = feed.videos[1].clone().build();
+        assert_eq!(video.title(), "Live: Q&A");
+        assert_eq!(video.description().unwrap(), "Questions from the community");
+        assert_eq!(video.duration(), None);
+    }
+
+    #[test]
+    fn parse_odysee() {
+        let feed = parse(include_str!("fixtures/odysee.xml"), None);
+
+        assert_eq!(feed.name, "Odysee");
+        assert_eq!(feed.videos.len(), 1);
+        assert!(feed.warnings.is_empty());
+
+        let video = feed.videos[0].clone().build();
+        assert_eq!(video.title(), "Creator update for January");
+        assert_eq!(video.link(), "https://odysee.com/@Odysee:8/creator-update-january:4");
+        assert_eq!(video.pub_date(), "2024-01-12T16:00:11+00:00");
+        assert_eq!(
+            video.description().unwrap(),
+            "What changed this month\n...and what comes next."
+        );
+        assert_eq!(video.thumbnail().unwrap(), "https://thumbnails.lbry.com/creator-update.jpg");
+        assert_eq!(video.duration(), Some(625));
+    }
+
+    #[test]
+    fn parse_json_feed() {
+        let text = include_str!("fixtures/json_feed.json");
+
+        for content_type in [Some("application/feed+json"), None] {
+            let feed = parse(text, content_type);
+
+            assert_eq!(feed.name, "Self-hosted Videos");
+            // the item without any link is dropped
+            assert_eq!(feed.videos.len(), 2);
+            assert!(feed.warnings.is_empty());
+
+            let video = feed.videos[0].clone().build();
+            assert_eq!(video.title(), "Building a cabin");
+            assert_eq!(video.link(), "https://videos.example.org/v/1");
+            assert_eq!(video.pub_date(), "2024-02-01T12:00:00+01:00");
+            assert_eq!(video.description().unwrap(), "Part one of the series.");
+            assert_eq!(video.duration(), Some(1261));
+
+            // no title and url: text and attachment are used instead
+            let video = feed.videos[1].clone().build();
+            assert_eq!(video.title(), "Short update");
+            assert_eq!(video.link(), "https://videos.example.org/media/2.webm");
+            assert_eq!(video.pub_date(), "2024-02-03T08:30:00+00:00");
+        }
+    }
+
+    #[test]
+    fn parse_json_feed_error() {
+        let error = Feed::parse_text("{\"title\": \"x\"}".to_string(), None).err().unwrap();
+        assert!(error.contains("json"));
+
+        let error = Feed::parse_text("<html></html>".to_string(), Some("text/html")).err().unwrap();
+        assert!(error.contains("atom") && error.contains("rss"));
     }
 }
@@ -21,6 +21,119 @@ pub struct Video {
     pub title: String,
     pub link: String,
     pub pub_date: String,
+    pub description: Option<String>,
+    pub thumbnail: Option<String>,
+    pub duration: Option<u64>,
+    pub views: Option<u64>,
+}
+
+// Optional metadata is read as text and dropped if it cannot be parsed, a feed with
+// a malformed attribute is still better than no feed at all.
+
+/// `media:content`, the duration is given in seconds
+#[derive(Debug, Deserialize)]
+struct MediaContent {
+    #[serde(rename = "@duration")]
+    duration: Option<String>,
+}
+
+impl MediaContent {
+    /// some feeds give fractions of a second
+    fn duration(&self) -> Option<u64> {
+        let seconds: f64 = self.duration.as_ref()?.trim().parse().ok()?;
+        (seconds.is_finite() && seconds >= 0.0).then(|| seconds.round() as u64)
+    }
+}
+
+/// `media:group`, used by PeerTube to list the files of a video
+#[derive(Debug, Deserialize)]
+struct MediaGroup {
+    #[serde(default)]
+    content: Vec<MediaContent>,
+}
+
+/// `media:community`
+#[derive(Debug, Deserialize)]
+struct MediaCommunity {
+    statistics: Option<MediaStatistics>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaStatistics {
+    #[serde(rename = "@views")]
+    views: Option<String>,
+}
+
+impl MediaStatistics {
+    fn views(&self) -> Option<u64> {
+        self.views.as_ref()?.trim().parse().ok()
+    }
+}
+
+/// `media:thumbnail`
+#[derive(Debug, Deserialize)]
+struct Thumbnail {
+    #[serde(rename = "@url")]
+    url: Option<String>,
+}
+
+/// `itunes:image`
+#[derive(Debug, Deserialize)]
+struct Image {
+    #[serde(rename = "@href")]
+    href: Option<String>,
+}
+
+/// Descriptions are often html (PeerTube, Odysee); keep the text and the line breaks
+pub fn html_to_text(html: &str) -> String {
+    if !html.contains('<') {
+        return html.trim().to_string();
+    }
+
+    let mut text = String::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        let tag = rest[start + 1..end].trim_start_matches('/').to_lowercase();
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').next();
+        if matches!(name, Some("br" | "p" | "div" | "li")) {
+            text.push('\n');
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    // collapse the empty lines left by images and paragraphs
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    lines.join("\n")
+}
+
+/// `src` of the first image in a html description, Odysee puts the thumbnail there
+fn first_image(html: &str) -> Option<String> {
+    let img = &html[html.find("<img")?..];
+    let src = &img[img.find("src=\"")? + 5..];
+    Some(src[..src.find('"')?].to_string())
+}
+
+/// parse `itunes:duration`, given as `[[hh:]mm:]ss`
+pub fn parse_duration(text: &str) -> Option<u64> {
+    text.trim()
+        .split(':')
+        .try_fold(0, |total, part| Some(total * 60 + part.parse::<u64>().ok()?))
 }
 
 impl<'de> Deserialize<'de> for Video {
@@ -46,6 +159,10 @@ impl<'de> Deserialize<'de> for Video {
                 let mut title = None;
                 let mut link = None;
                 let mut pub_date = None;
+                let mut description = None;
+                let mut thumbnail = None;
+                let mut duration = None;
+                let mut views = None;
                 while let Some(key) = match map.next_key::<String>() {
                     Ok(s) => s,
                     Err(e) => return Err(e),
@@ -66,6 +183,45 @@ impl<'de> Deserialize<'de> for Video {
                             }
                             pub_date = Some(map.next_value()?);
                         }
+                        // media:description is plain text and preferred
+                        "description" => {
+                            let text: String = map.next_value()?;
+                            thumbnail = thumbnail.or(first_image(&text));
+                            if description.is_none() || !text.contains('<') {
+                                description = Some(html_to_text(&text));
+                            }
+                        }
+                        // itunes:duration
+                        "duration" => {
+                            let text: String = map.next_value()?;
+                            duration = duration.or(parse_duration(&text));
+                        }
+                        // media:content
+                        "content" => {
+                            let content: MediaContent = map.next_value()?;
+                            duration = duration.or(content.duration());
+                        }
+                        // media:group
+                        "group" => {
+                            let group: MediaGroup = map.next_value()?;
+                            let group_duration = group.content.iter().find_map(MediaContent::duration);
+                            duration = duration.or(group_duration);
+                        }
+                        // media:community
+                        "community" => {
+                            let community: MediaCommunity = map.next_value()?;
+                            views = views.or(community.statistics.and_then(|s| s.views()));
+                        }
+                        // media:thumbnail
+                        "thumbnail" => {
+                            let image: Thumbnail = map.next_value()?;
+                            thumbnail = thumbnail.or(image.url.filter(|url| !url.is_empty()));
+                        }
+                        // itunes:image
+                        "image" => {
+                            let image: Image = map.next_value()?;
+                            thumbnail = thumbnail.or(image.href.filter(|href| !href.is_empty()));
+                        }
                         _ => map.next_value()?,
                     }
                 }
@@ -77,6 +233,10 @@ impl<'de> Deserialize<'de> for Video {
                     title,
                     link,
                     pub_date,
+                    description,
+                    thumbnail,
+                    duration,
+                    views,
                 };
                 Ok(video)
             }
@@ -0,0 +1,308 @@
+use crate::backend::data::video::Video;
+use fancy_regex::Regex;
+use log::*;
+use serde::{Deserialize, Serialize};
+
+/// Allow and block rules as written in the config or a subscription.
+///
+/// A video is kept if it matches any `allow` rule (or there are none) and no `block` rule.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FilterRules {
+    #[serde(default)]
+    pub allow: Vec<FilterRule>,
+    #[serde(default)]
+    pub block: Vec<FilterRule>,
+}
+
+/// All given conditions have to match. Regexes are matched anywhere in the text,
+/// durations are in seconds and ages in days.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FilterRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// falls back to the original title if dearrow has no title for the video
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dearrow_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_age: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<i64>,
+    /// feed url the video came from, to filter single feeds of a custom channel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin_url: Option<String>,
+}
+
+impl FilterRules {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.block.is_empty()
+    }
+}
+
+/// Compiled rules of the config and of one subscription; a video has to pass all of them
+#[derive(Default)]
+pub(crate) struct Filter {
+    rule_sets: Vec<RuleSet>,
+}
+
+struct RuleSet {
+    allow: Vec<Rule>,
+    block: Vec<Rule>,
+}
+
+struct Rule {
+    title: Option<Regex>,
+    dearrow_title: Option<Regex>,
+    description: Option<Regex>,
+    min_duration: Option<u64>,
+    max_duration: Option<u64>,
+    min_age: Option<i64>,
+    max_age: Option<i64>,
+    origin_url: Option<Regex>,
+}
+
+impl Filter {
+    /// Compile `rules`; `block_regex` is added as block rule on the title.
+    /// Fails with the first regex that is invalid.
+    pub(crate) fn new(
+        rules: &[&FilterRules],
+        block_regex: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut rule_sets = rules
+            .iter()
+            .filter(|rules| !rules.is_empty())
+            .map(|rules| RuleSet::new(rules))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(regex) = block_regex {
+            let rule = FilterRule {
+                title: Some(regex.to_string()),
+                ..FilterRule::default()
+            };
+            rule_sets.push(RuleSet {
+                allow: Vec::new(),
+                block: vec![Rule::new(&rule)?],
+            });
+        }
+
+        Ok(Self { rule_sets })
+    }
+
+    /// Whether `video` passes all rules. While dearrow titles are still being fetched
+    /// (`dearrow_pending`), conditions on them neither allow nor block a video.
+    pub(crate) fn keep(&self, video: &Video, dearrow_pending: bool) -> bool {
+        self.rule_sets.iter().all(|set| set.keep(video, dearrow_pending))
+    }
+}
+
+impl RuleSet {
+    fn new(rules: &FilterRules) -> Result<Self, String> {
+        Ok(Self {
+            allow: rules.allow.iter().map(Rule::new).collect::<Result<_, _>>()?,
+            block: rules.block.iter().map(Rule::new).collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn keep(&self, video: &Video, dearrow_pending: bool) -> bool {
+        let allowed = self.allow.is_empty()
+            || self
+                .allow
+                .iter()
+                .any(|rule| rule.matches(video, dearrow_pending) != Some(false));
+
+        let blocked = self
+            .block
+            .iter()
+            .any(|rule| rule.matches(video, dearrow_pending) == Some(true));
+
+        allowed && !blocked
+    }
+}
+
+impl Rule {
+    fn new(rule: &FilterRule) -> Result<Self, String> {
+        let compile = |regex: &Option<String>| {
+            regex
+                .as_deref()
+                .map(|regex| Regex::new(regex).map_err(|error| format!("'{}': {}", regex, error)))
+                .transpose()
+        };
+
+        Ok(Self {
+            title: compile(&rule.title)?,
+            dearrow_title: compile(&rule.dearrow_title)?,
+            description: compile(&rule.description)?,
+            min_duration: rule.min_duration,
+            max_duration: rule.max_duration,
+            min_age: rule.min_age,
+            max_age: rule.max_age,
+            origin_url: compile(&rule.origin_url)?,
+        })
+    }
+
+    /// `None` if the result depends on a dearrow title that is not known yet
+    fn matches(&self, video: &Video, dearrow_pending: bool) -> Option<bool> {
+        // e.g. the backtrack limit was hit; such a rule does not match
+        let is_match = |regex: &Regex, text: &str| match regex.is_match(text) {
+            Ok(is_match) => is_match,
+            Err(error) => {
+                warn!("Could not match '{}' on '{}': {}", regex.as_str(), text, error);
+                false
+            }
+        };
+
+        let title = self.title.as_ref().is_none_or(|re| is_match(re, video.title()));
+        let description = self.description.as_ref().is_none_or(|re| {
+            video.description().is_some_and(|text| is_match(re, text))
+        });
+        let origin_url = self
+            .origin_url
+            .as_ref()
+            .is_none_or(|re| is_match(re, video.origin_url()));
+
+        let duration = match (self.min_duration, self.max_duration) {
+            (None, None) => true,
+            (min, max) => video.duration().is_some_and(|duration| {
+                min.is_none_or(|min| duration >= min) && max.is_none_or(|max| duration <= max)
+            }),
+        };
+
+        let age = match (self.min_age, self.max_age) {
+            (None, None) => true,
+            (min, max) => video.age_in_days().is_some_and(|age| {
+                min.is_none_or(|min| age >= min) && max.is_none_or(|max| age <= max)
+            }),
+        };
+
+        if !(title && description && origin_url && duration && age) {
+            return Some(false);
+        }
+
+        match (&self.dearrow_title, video.dearrow_title()) {
+            (None, _) => Some(true),
+            (Some(re), Some(dearrow_title)) => Some(is_match(re, dearrow_title)),
+            (Some(_), None) if dearrow_pending => None,
+            (Some(re), None) => Some(is_match(re, video.title())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::data::video::builder::VideoBuilder;
+    use chrono::{Duration, Local};
+
+    fn video(title: &str, description: Option<&str>, duration: Option<u64>, age: i64) -> Video {
+        let mut builder = VideoBuilder::create();
+        builder.set_title(title.to_string());
+        builder.set_link(format!("https://example.com/{}", title));
+        builder.set_description(description.map(str::to_string));
+        builder.set_duration(duration);
+        builder.set_origin_url("https://example.com/feed.xml");
+        let date = Local::now() - Duration::days(age);
+        builder.set_pub_date_from_feed(&date.to_rfc3339());
+        builder.build()
+    }
+
+    fn rules(allow: Vec<FilterRule>, block: Vec<FilterRule>) -> FilterRules {
+        FilterRules { allow, block }
+    }
+
+    fn title(regex: &str) -> FilterRule {
+        FilterRule {
+            title: Some(regex.to_string()),
+            ..FilterRule::default()
+        }
+    }
+
+    #[test]
+    fn empty_filter_keeps_everything() {
+        let filter = Filter::new(&[&FilterRules::default()], None).unwrap();
+        assert!(filter.keep(&video("Anything", None, None, 0), false));
+    }
+
+    #[test]
+    fn allow_is_include_only() {
+        let rules = rules(vec![title("Podcast")], Vec::new());
+        let filter = Filter::new(&[&rules], None).unwrap();
+
+        assert!(filter.keep(&video("Podcast #12", None, None, 0), false));
+        assert!(!filter.keep(&video("Vlog", None, None, 0), false));
+    }
+
+    #[test]
+    fn block_wins_over_allow() {
+        let block = FilterRule {
+            description: Some("(?i)sponsored".to_string()),
+            ..FilterRule::default()
+        };
+        let rules = rules(vec![title("Podcast")], vec![block]);
+        let filter = Filter::new(&[&rules], None).unwrap();
+
+        assert!(filter.keep(&video("Podcast", Some("Guests"), None, 0), false));
+        assert!(!filter.keep(&video("Podcast", Some("This is Sponsored"), None, 0), false));
+    }
+
+    #[test]
+    fn duration_and_age_ranges() {
+        let short = FilterRule {
+            max_duration: Some(60),
+            ..FilterRule::default()
+        };
+        let old = FilterRule {
+            min_age: Some(30),
+            ..FilterRule::default()
+        };
+        let rules = rules(Vec::new(), vec![short, old]);
+        let filter = Filter::new(&[&rules], None).unwrap();
+
+        assert!(filter.keep(&video("Long", None, Some(600), 2), false));
+        assert!(!filter.keep(&video("Short", None, Some(45), 2), false));
+        assert!(!filter.keep(&video("Old", None, Some(600), 40), false));
+        // unknown durations are never in a range
+        assert!(filter.keep(&video("Unknown", None, None, 2), false));
+    }
+
+    #[test]
+    fn config_and_subscription_rules_both_apply() {
+        let global = rules(Vec::new(), vec![title("Shorts")]);
+        let origin = FilterRule {
+            origin_url: Some("example.com".to_string()),
+            ..FilterRule::default()
+        };
+        let subscription = rules(vec![origin], Vec::new());
+        let filter = Filter::new(&[&global, &subscription], Some("FUNNY")).unwrap();
+
+        assert!(filter.keep(&video("Talk", None, None, 0), false));
+        assert!(!filter.keep(&video("Shorts compilation", None, None, 0), false));
+        assert!(!filter.keep(&video("EXTREMELY FUNNY", None, None, 0), false));
+    }
+
+    #[test]
+    fn dearrow_rules_wait_for_dearrow_titles() {
+        let dearrow = FilterRule {
+            dearrow_title: Some("Podcast".to_string()),
+            ..FilterRule::default()
+        };
+        let filter = Filter::new(&[&rules(vec![dearrow], Vec::new())], None).unwrap();
+
+        let video = video("You won't BELIEVE this", None, None, 0);
+        assert!(filter.keep(&video, true));
+        // without a dearrow title the original title is used
+        assert!(!filter.keep(&video, false));
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        let rules = rules(vec![title("(unclosed")], Vec::new());
+        assert!(Filter::new(&[&rules], None).is_err());
+        assert!(Filter::new(&[], Some("[")).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Upgrades of the persisted `ChannelList` (`db.json`) from older releases.
+//!
+//! Every change of the stored format increases [`DB_VERSION`] and adds a step to
+//! [`MIGRATIONS`] that turns a file of the previous version into the next one.
+
+use crate::backend::{Error::NewerDbVersion, Result};
+use serde_json::{Map, Value};
+
+/// Version of the DB format written by this build. Files without a version are 0.
+pub(crate) const DB_VERSION: u64 = 2;
+
+/// `MIGRATIONS[i]` upgrades version `i` to `i + 1`
+const MIGRATIONS: [fn(&mut Map<String, Value>); DB_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+/// Upgrade `db` step by step to [`DB_VERSION`].
+/// Files written by a newer version are an error, they would lose data when saved again.
+pub(crate) fn migrate(db: &mut Value) -> Result<()> {
+    // anything else is left to the deserializer to complain about
+    let Some(db) = db.as_object_mut() else {
+        return Ok(());
+    };
+
+    let version = db.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > DB_VERSION {
+        return Err(NewerDbVersion(version, DB_VERSION));
+    }
+
+    for step in &MIGRATIONS[version as usize..] {
+        step(db);
+    }
+    db.insert("version".to_string(), DB_VERSION.into());
+
+    Ok(())
+}
+
+fn channels(db: &mut Map<String, Value>) -> impl Iterator<Item = &mut Map<String, Value>> {
+    objects(db, "channels")
+}
+
+/// objects in the array `key` of `object`
+fn objects<'a>(object: &'a mut Map<String, Value>, key: &str) -> impl Iterator<Item = &'a mut Map<String, Value>> {
+    object
+        .get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+/// Releases up to 2.0.5: `sorting_method` was written but never read, it comes from the
+/// subscriptions. `fav` and `hidden_videos` did not exist in all of them.
+fn v0_to_v1(db: &mut Map<String, Value>) {
+    for channel in channels(db) {
+        channel.remove("sorting_method");
+        channel
+            .entry("hidden_videos")
+            .or_insert_with(|| Value::Array(Vec::new()));
+
+        for video in objects(channel, "videos") {
+            video.entry("fav").or_insert(Value::Bool(false));
+        }
+    }
+}
+
+/// Videos know when their watched state was changed last, for syncing between machines.
+/// Older changes are unknown.
+fn v1_to_v2(db: &mut Map<String, Value>) {
+    for channel in channels(db) {
+        for key in ["videos", "hidden_videos"] {
+            for video in objects(channel, key) {
+                video.entry("modified").or_insert(Value::from(0));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::data::channel_list::ChannelList;
+
+    const UNVERSIONED: &str = include_str!("fixtures/db_2.0.5.json");
+    const UNVERSIONED_MINIMAL: &str = include_str!("fixtures/db_minimal.json");
+    const NEWER: &str = include_str!("fixtures/db_newer.json");
+
+    #[test]
+    fn upgrades_unversioned_files() {
+        let mut db: Value = serde_json::from_str(UNVERSIONED).unwrap();
+        migrate(&mut db).unwrap();
+
+        assert_eq!(db["version"], DB_VERSION);
+        assert!(db["channels"][0].get("sorting_method").is_none());
+        assert_eq!(db["channels"][0]["hidden_videos"], Value::Array(Vec::new()));
+        assert_eq!(db["channels"][0]["videos"][0]["modified"], 0);
+
+        let channel_list = ChannelList::from_json(UNVERSIONED).unwrap();
+        let channel = &channel_list.channels()[0];
+        assert_eq!(channel.name(), "Rust");
+        assert_eq!(channel.videos.len(), 2);
+        assert!(channel.videos[0].marked());
+        assert!(channel.videos[1].is_fav());
+    }
+
+    #[test]
+    fn upgrades_files_without_optional_fields() {
+        let channel_list = ChannelList::from_json(UNVERSIONED_MINIMAL).unwrap();
+        let video = &channel_list.channels()[0].videos[0];
+
+        assert!(!video.is_fav());
+        assert!(video.dearrow_title().is_none());
+    }
+
+    #[test]
+    fn current_files_are_unchanged() {
+        let channel_list = ChannelList::from_json(UNVERSIONED).unwrap();
+        let json = serde_json::to_string(&channel_list).unwrap();
+
+        let mut db: Value = serde_json::from_str(&json).unwrap();
+        let before = db.clone();
+        migrate(&mut db).unwrap();
+        assert_eq!(db, before);
+    }
+
+    #[test]
+    fn refuses_newer_files() {
+        let error = ChannelList::from_json(NEWER).unwrap_err();
+        assert!(matches!(error, NewerDbVersion(99, DB_VERSION)));
+    }
+}
@@ -1,85 +1,119 @@
-use fancy_regex::Regex;
-
 use self::channel_list::ChannelList;
 use crate::{
     backend::{
         core::{FetchState, StateUpdate},
-        data::channel::Channel,
-        data::feed::Feed,
+        data::{channel::Channel, filter::Filter, video::builder::VideoBuilder},
+        data::feed::{Feed, FeedError},
         io::subscriptions::{SubscriptionItem, Subscriptions},
         io::config::Config,
+        io::feed_cache::{CacheEntry, FeedCache, FeedValidators},
+        http::{build_client, execute, HostLimiter, RetryPolicy},
     },
     notification::notify_error,
 };
-use reqwest::blocking::Client;
+use reqwest::{
+    blocking::Client,
+    header::{HeaderMap, CONTENT_TYPE, IF_MODIFIED_SINCE, IF_NONE_MATCH},
+    StatusCode,
+};
 use std::sync::{
     mpsc::channel,
     mpsc::{Receiver, Sender, TryRecvError},
+    Arc, Mutex,
 };
 use threadpool::ThreadPool;
 use log::*;
 
 pub(crate) mod channel;
 pub(crate) mod channel_list;
-mod feed;
+pub(crate) mod feed;
+pub(crate) mod filter;
+pub(crate) mod migration;
+pub(crate) mod stats;
 pub(crate) mod video;
+pub(crate) mod watch_state;
 pub mod downloader;
 
 pub(crate) struct Data {
-    sender: Sender<Channel>,
-    receiver: Receiver<Channel>,
+    sender: Sender<ChannelUpdate>,
+    receiver: Receiver<ChannelUpdate>,
     status_sender: Sender<StateUpdate>,
+    fetcher: Fetcher,
+}
+
+/// A fetched channel and the validators of its feeds, which are only valid once the
+/// channel is stored
+pub(crate) type ChannelUpdate = (Channel, Option<FeedValidators>);
+
+/// Everything needed to download feeds, shared by all fetch threads
+#[derive(Clone)]
+pub(crate) struct Fetcher {
+    client: Client,
+    limiter: Arc<HostLimiter>,
+    retry_policy: RetryPolicy,
+    feed_cache: Arc<Mutex<FeedCache>>,
 }
 
 impl Data {
     /// Init
-    pub(crate) fn init(status_sender: Sender<StateUpdate>) -> Self {
+    pub(crate) fn init(status_sender: Sender<StateUpdate>, config: &Config) -> crate::backend::Result<Self> {
         let (sender, receiver) = channel();
 
-        Self {
+        let fetcher = Fetcher::new(config)?;
+
+        Ok(Self {
             sender,
             receiver,
             status_sender,
-        }
+            fetcher,
+        })
     }
 
     /// try receive data that was newly fetched
-    pub(crate) fn try_recv(&self) -> Result<Channel, TryRecvError> {
+    pub(crate) fn try_recv(&self) -> Result<ChannelUpdate, TryRecvError> {
         self.receiver.try_recv()
     }
 
+    /// Remember the validators of subscription `id` once its videos are stored, so they are
+    /// never ahead of the DB
+    pub(crate) fn save_validators(&self, id: &str, validators: FeedValidators) {
+        let mut feed_cache = self.fetcher.feed_cache.lock().unwrap();
+        feed_cache.set(id, validators);
+        feed_cache.save();
+    }
+
     /// start fetching process
     pub(crate) fn update(&self, config: &Config) {
         info!("Starting Update");
         let subs = match Subscriptions::read() {
             Ok(subs) => subs,
             Err(error) => {
-                notify_error(&format!("Could not fetch updates: {:?}", error));
+                notify_error(&format!("Could not fetch updates: {}", error));
                 return;
             }
         };
 
         // load already known items
-        let history = match ChannelList::load() {
+        let history = match ChannelList::load(config) {
             Ok(history) => history,
             Err(error) => {
-                notify_error(&format!("Could not fetch updates: {:?}", error));
+                notify_error(&format!("Could not fetch updates: {}", error));
                 return;
             }
         };
 
-        // prepate threads
-        let worker_num = 10;
-        let pool = ThreadPool::new(worker_num);
+        // prepate threads; requests per host are limited separately
+        let pool = ThreadPool::new(config.fetch_workers.max(1));
 
         // load "normal" channels
         for item in subs.channels {
             let sender_clone = self.sender.clone();
             let hc = history.clone();
             let item = item.clone();
-            let urls = vec![item.url.clone()];
+            let urls = item.feed_urls(config);
             let block_regex = item.block_regex().clone();
             let config = config.clone();
+            let fetcher = self.fetcher.clone();
 
             let sender = self.status_sender.clone();
             pool.execute(move || {
@@ -91,6 +125,7 @@ impl Data {
                     block_regex,
                     sender,
                     config,
+                    fetcher,
                 ); // updates will be send with `channel_sender`
             })
         }
@@ -100,9 +135,10 @@ impl Data {
             let sender_clone = self.sender.clone();
             let hc = history.clone();
             let item = item.clone();
-            let urls = item.urls.clone();
+            let urls = item.feed_urls(config);
             let block_regex = item.block_regex().clone();
             let config = config.clone();
+            let fetcher = self.fetcher.clone();
 
             let sender = self.status_sender.clone();
             pool.execute(move || {
@@ -114,20 +150,23 @@ impl Data {
                     block_regex,
                     sender,
                     config,
+                    fetcher,
                 ); // updates will be send with `channel_sender`
             })
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn fetch_channel_updates<T: 'static + SubscriptionItem + std::marker::Send>(
-    channel_sender: Sender<Channel>,
+    channel_sender: Sender<ChannelUpdate>,
     history: ChannelList,
     item: T,
     urls: Vec<String>,
     block_regex: Option<String>,
     status_sender: Sender<StateUpdate>,
     config: Config,
+    fetcher: Fetcher,
 ) {
     // get videos from history file
     let (history_videos, history_name) = match history.get_by_id(&item.id()) {
@@ -141,7 +180,46 @@ fn fetch_channel_updates<T: 'static + SubscriptionItem + std::marker::Send>(
             .unwrap();
     }
 
-    let (mut feed, num_failed) = download_feed(&urls);
+    let hide_shorts = item.hide_shorts().unwrap_or(config.hide_shorts);
+    let hide_upcoming = item.hide_upcoming().unwrap_or(config.hide_upcoming);
+
+    // validators only fit the stored videos if they were filtered the same way
+    let settings = (
+        &urls,
+        &config.filter,
+        item.filter(),
+        &block_regex,
+        hide_shorts,
+        hide_upcoming,
+        config.use_dearrow_titles,
+    );
+    let mut validators = fetcher.feed_cache.lock().unwrap().validators(&item.id(), &settings);
+    validators.retain_urls(&urls);
+
+    // only ask for changes if we actually know the videos of an earlier download
+    if history_videos.is_empty() {
+        validators.clear();
+    }
+    let (mut feed, mut errors) = fetcher.download_feed(&urls, &mut validators);
+
+    // a running stream is shown as first entry of the channel
+    if let (Some(name), Some(template)) = (item.twitch_channel(), &config.twitch_live_check) {
+        let url = template.replace("{channel}", &name);
+        match fetcher.is_live(&url) {
+            Ok(true) => {
+                let mut live = VideoBuilder::live(&name, format!("https://www.twitch.tv/{}", name));
+                live.set_origin_url(&url);
+                live.set_origin_channel_name(&name);
+                feed.add_videos(vec![live]);
+            }
+            Ok(false) => {}
+            Err(error) => {
+                warn!("Could not check live status of {}: {}", name, error);
+                errors.push((url, error));
+            }
+        }
+    }
+    let warnings = std::mem::take(&mut feed.warnings);
 
     // choose item name first; if not given, take feed name; take history name as last resort
     let name = if !item.name().is_empty() {
@@ -152,16 +230,28 @@ fn fetch_channel_updates<T: 'static + SubscriptionItem + std::marker::Send>(
         history_name
     };
 
+    let filter = match Filter::new(&[&config.filter, item.filter()], block_regex.as_deref()) {
+        Ok(filter) => filter,
+        Err(error) => {
+            notify_error(&format!("Invalid filter of {}: {}", item.id(), error));
+            Filter::default()
+        }
+    };
+
     let mut channel_builder = Channel::builder();
 
-    // only add new videos if active
-    if item.active() {
-        if let Some(regex) = block_regex {
-            let re = Regex::new(&regex).unwrap();
-            feed.filter_videos(re);
+    // only add new videos if active; the validators are only kept for videos that are added
+    let validators = if item.active() {
+        feed.filter_videos(&filter, config.use_dearrow_titles);
+        // upcoming videos are added once their time has come, even if the feed does not change
+        if feed.filter_kinds(hide_shorts, hide_upcoming) {
+            validators.clear();
         }
-        channel_builder = channel_builder.add_from_feed(feed)
-    }
+        channel_builder = channel_builder.add_from_feed(feed);
+        Some(validators)
+    } else {
+        None
+    };
 
     let channel_builder = channel_builder.with_old_videos(history_videos)
         .with_name(name)
@@ -170,72 +260,159 @@ fn fetch_channel_updates<T: 'static + SubscriptionItem + std::marker::Send>(
         .with_sorting(item.sorting_method());
 
 
-    // send channel without dearrow titles to main thread
+    // send channel without dearrow titles to main thread;
+    // the validators are sent with the last update of the channel
     let channel = channel_builder.clone().build();
-    let _ = channel_sender.send(channel);
-
-    // if we want to use dearrow titles, fetch them now and send them to the main thread
-    if config.use_dearrow_titles {
+    if !config.use_dearrow_titles {
+        let _ = channel_sender.send((channel, validators));
+    } else {
+        let _ = channel_sender.send((channel, None));
 
+        // fetch dearrow titles now and send them to the main thread
         let state = FetchState::FetchingDearrow;
         let _ = status_sender.send(StateUpdate::new(item.id(), state));
 
+        // rules on dearrow titles can only be applied now
         let channel = channel_builder
-            .use_dearrow()
+            .use_dearrow(&fetcher)
+            .filter_new_videos(&filter)
             .build();
-        let _ = channel_sender.send(channel);
+        let _ = channel_sender.send((channel, validators));
     }
 
     // send status to main thread
-    let state = if num_failed > 0 {
-        FetchState::DownloadsFailure(num_failed)
+    let state = if !errors.is_empty() {
+        FetchState::DownloadsFailure(errors)
+    } else if !warnings.is_empty() {
+        FetchState::FetchedWithWarnings(warnings)
     } else {
         FetchState::Fetched
     };
     let _ = status_sender.send(StateUpdate::new(item.id(), state));
 }
 
-// download xml and parse
-// returns Feed and number of download/parsing failures
-fn download_feed(urls: &[String]) -> (Feed, usize) {
-    let client = Client::builder().build().unwrap();
-
-    let mut feed_final = Feed::default();
+impl Fetcher {
+    pub(crate) fn new(config: &Config) -> crate::backend::Result<Self> {
+        Ok(Self {
+            client: build_client(config)?,
+            limiter: Arc::new(HostLimiter::from(config)),
+            retry_policy: RetryPolicy::from(config),
+            feed_cache: Arc::new(Mutex::new(FeedCache::load())),
+        })
+    }
 
-    let mut num_failed = 0;
+    // download xml and parse
+    // returns Feed and the errors of all urls that failed
+    // feeds that did not change since `validators` were received (304) contribute no videos,
+    // the validators of all others are updated
+    fn download_feed(&self, urls: &[String], validators: &mut FeedValidators) -> (Feed, Vec<(String, FeedError)>) {
+        let mut feed_final = Feed::default();
+
+        let mut errors = Vec::new();
+
+        // one internal feed can consist of seveal "normal" feeds
+        for url in urls.iter() {
+            let (mut feed, headers) = match self.fetch_url(url, validators.get(url).cloned()) {
+                Ok(Some(download)) => download,
+                Ok(None) => {
+                    debug!("Feed not modified: {}", url);
+                    continue;
+                }
+                Err(error) => {
+                    warn!("Could not fetch {}: {}", url, error);
+                    errors.push((url.clone(), error));
+                    continue;
+                }
+            };
+
+            // remember validators only for feeds we could actually use
+            validators.update(url, &headers);
+
+            for warning in feed.warnings.drain(..) {
+                warn!("{}: {}", url, warning);
+                feed_final.warnings.push(warning);
+            }
 
-    // one internal feed can consist of seveal "normal" feeds
-    for url in urls.iter() {
-        // download feed
-        let text = match client.get(url).send() {
-            Ok(res) => res.text().unwrap_or_default(),
-            Err(_) => {
-                num_failed += 1;
-                continue;
+            // set some meta on videos
+            for vf in feed.videos.iter_mut() {
+                vf.set_origin_url(url);
+                vf.set_origin_channel_name(&feed.name);
             }
-        };
 
-        // parse feed
-        let mut feed = match Feed::parse_text(text) {
-            Ok(f) => f,
-            Err(_) => {
-                num_failed += 1;
-                continue;
+            // add to final feed
+            feed_final.add_videos(feed.videos);
+            feed_final.set_name(&feed.name);
+        }
+
+        (feed_final, errors)
+    }
+
+    /// Download and parse a single url. Returns `None` if the server reports that the
+    /// feed did not change since `validators` were received.
+    fn fetch_url(
+        &self,
+        url: &str,
+        validators: Option<CacheEntry>,
+    ) -> Result<Option<(Feed, HeaderMap)>, FeedError> {
+        let mut request = self.client.get(url);
+
+        if let Some(entry) = validators {
+            if let Some(etag) = entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
             }
-        };
+            if let Some(last_modified) = entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let res = request
+            .build()
+            .and_then(|req| execute(&self.client, req, &self.limiter, &self.retry_policy))
+            .map_err(|error| FeedError::Network(error.to_string()))?;
 
-        // set some meta on videos
-        for vf in feed.videos.iter_mut() {
-            vf.set_origin_url(url);
-            vf.set_origin_channel_name(&feed.name);
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
         }
 
-        // add to final feed
-        feed_final.add_videos(feed.videos);
-        feed_final.set_name(&feed.name);
+        if !res.status().is_success() {
+            return Err(FeedError::Http(res.status().as_u16()));
+        }
+
+        let headers = res.headers().clone();
+        let content_type = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok());
+        let text = res.text().map_err(|error| FeedError::Network(error.to_string()))?;
+
+        let feed = Feed::parse_text(text, content_type).map_err(FeedError::Parse)?;
+
+        Ok(Some((feed, headers)))
     }
 
-    (feed_final, num_failed)
+    /// Check whether the twitch page `url` belongs to a channel that is streaming right now
+    fn is_live(&self, url: &str) -> Result<bool, FeedError> {
+        let res = self
+            .client
+            .get(url)
+            .build()
+            .and_then(|req| execute(&self.client, req, &self.limiter, &self.retry_policy))
+            .map_err(|error| FeedError::Network(error.to_string()))?;
+
+        if !res.status().is_success() {
+            return Err(FeedError::Http(res.status().as_u16()));
+        }
+
+        let text = res.text().map_err(|error| FeedError::Network(error.to_string()))?;
+
+        // the page embeds structured data with this flag only while a stream is running
+        Ok(text.contains("\"isLiveBroadcast\":true"))
+    }
+
+    /// Download and parse `url` without touching the cache; returns the number of videos
+    pub(crate) fn check(&self, url: &str) -> Result<usize, FeedError> {
+        match self.fetch_url(url, None)? {
+            Some((feed, _)) => Ok(feed.videos.len()),
+            None => Ok(0),
+        }
+    }
 }
 
 /* #[cfg(test)]
@@ -0,0 +1,277 @@
+//! Numbers about the opened and the stored videos, shown in the statistics popup
+//! and printed by `tyt stats`.
+
+use crate::backend::{
+    data::{channel_list::ChannelList, video::Video},
+    io::{history::MinimalVideo, open_counts::OpenCounts},
+};
+use chrono::{DateTime, FixedOffset, Local, Months, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Rows shown per section of the report; `--json` always has everything
+const DAYS_SHOWN: usize = 14;
+const WEEKS_SHOWN: usize = 8;
+const CHANNELS_SHOWN: usize = 10;
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Stats {
+    /// opened videos per local day, `YYYY-MM-DD`
+    watched_per_day: BTreeMap<String, usize>,
+    /// opened videos per ISO week, `YYYY-Www`
+    watched_per_week: BTreeMap<String, usize>,
+    /// most opened first
+    top_channels: Vec<Count>,
+    /// unseen videos, largest backlog first
+    backlog_per_channel: Vec<Count>,
+    backlog_per_tag: Vec<Count>,
+    oldest_unseen: Option<UnseenVideo>,
+    /// channels without upload for `stale_after_months`, longest silence first
+    stale_channels: Vec<StaleChannel>,
+    stale_after_months: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Count {
+    name: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct UnseenVideo {
+    title: String,
+    channel: String,
+    published: String,
+}
+
+#[derive(Debug, Serialize)]
+struct StaleChannel {
+    name: String,
+    /// `None` if the channel has no videos at all
+    last_upload: Option<String>,
+}
+
+impl Stats {
+    /// `history` only names channels that were removed from the channel list
+    pub(crate) fn collect(
+        channel_list: &ChannelList,
+        open_counts: &OpenCounts,
+        history: &[MinimalVideo],
+        stale_after_months: u32,
+    ) -> Self {
+        let mut stats = Self {
+            stale_after_months,
+            watched_per_day: open_counts.per_day().clone(),
+            ..Self::default()
+        };
+
+        // --- opened videos ---
+        for (day, count) in open_counts.per_day() {
+            if let Ok(day) = NaiveDate::parse_from_str(day, "%Y-%m-%d") {
+                *stats.watched_per_week.entry(day.format("%G-W%V").to_string()).or_default() += count;
+            }
+        }
+
+        let mut per_channel: HashMap<&str, usize> = HashMap::new();
+        for (id, count) in open_counts.per_channel() {
+            let name = match channel_list.get_unfiltered_by_id(id) {
+                Some(channel) => channel.name().as_str(),
+                None => history
+                    .iter()
+                    .find(|entry| entry.channel_id() == id)
+                    .map_or(id.as_str(), |entry| entry.channel().as_str()),
+            };
+            *per_channel.entry(name).or_default() += count;
+        }
+        stats.top_channels = sorted_counts(per_channel);
+
+        // --- stored videos ---
+        let stale_since = Local::now().checked_sub_months(Months::new(stale_after_months));
+        let mut per_tag: HashMap<&str, usize> = HashMap::new();
+        let mut per_channel: HashMap<&str, usize> = HashMap::new();
+        let mut oldest_unseen: Option<(DateTime<FixedOffset>, &Video, &str)> = None;
+        let mut stale = Vec::new();
+
+        for channel in channel_list.channels().iter().filter(|channel| !channel.id().is_empty()) {
+            let unseen: Vec<&Video> = channel.videos.iter().filter(|video| !video.marked()).collect();
+
+            if !unseen.is_empty() {
+                per_channel.insert(channel.name(), unseen.len());
+                if !channel.tag().is_empty() {
+                    *per_tag.entry(channel.tag()).or_default() += unseen.len();
+                }
+            }
+
+            for video in unseen {
+                let Some(date) = published(video) else { continue };
+                if oldest_unseen.as_ref().is_none_or(|(oldest, _, _)| date < *oldest) {
+                    oldest_unseen = Some((date, video, channel.name()));
+                }
+            }
+
+            let last_upload = channel
+                .videos
+                .iter()
+                .chain(channel.hidden_videos.iter())
+                .filter_map(published)
+                .max();
+            let is_stale = match (last_upload, stale_since) {
+                (Some(date), Some(since)) => date < since,
+                (None, _) => true,
+                (Some(_), None) => false,
+            };
+            if is_stale {
+                stale.push((last_upload, channel.name()));
+            }
+        }
+
+        stats.backlog_per_channel = sorted_counts(per_channel);
+        stats.backlog_per_tag = sorted_counts(per_tag);
+        stats.oldest_unseen = oldest_unseen.map(|(date, video, channel)| UnseenVideo {
+            title: video.title().clone(),
+            channel: channel.to_string(),
+            published: date.format("%Y-%m-%d").to_string(),
+        });
+
+        // channels without any video first
+        stale.sort();
+        stats.stale_channels = stale
+            .into_iter()
+            .map(|(last_upload, name)| StaleChannel {
+                name: name.to_string(),
+                last_upload: last_upload.map(|date| date.format("%Y-%m-%d").to_string()),
+            })
+            .collect();
+
+        stats
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Headings with their rows, for the popup and the plain text output
+    pub(crate) fn report(&self) -> Vec<(String, Vec<String>)> {
+        let newest = |map: &BTreeMap<String, usize>, n: usize| {
+            let skip = map.len().saturating_sub(n);
+            map.iter()
+                .skip(skip)
+                .map(|(key, count)| format!("{}  {}", key, count))
+                .collect()
+        };
+        let counts = |counts: &[Count], n: usize| {
+            counts
+                .iter()
+                .take(n)
+                .map(|count| format!("{}  {}", count.name, count.count))
+                .collect()
+        };
+
+        let oldest_unseen = self
+            .oldest_unseen
+            .iter()
+            .map(|video| format!("{} - {} ({})", video.channel, video.title, video.published))
+            .collect();
+        let stale_channels = self
+            .stale_channels
+            .iter()
+            .map(|channel| match &channel.last_upload {
+                Some(date) => format!("{}  last upload {}", channel.name, date),
+                None => format!("{}  no videos", channel.name),
+            })
+            .collect();
+
+        vec![
+            ("Watched per day".to_string(), newest(&self.watched_per_day, DAYS_SHOWN)),
+            ("Watched per week".to_string(), newest(&self.watched_per_week, WEEKS_SHOWN)),
+            ("Most watched channels".to_string(), counts(&self.top_channels, CHANNELS_SHOWN)),
+            ("Unseen videos per channel".to_string(), counts(&self.backlog_per_channel, usize::MAX)),
+            ("Unseen videos per tag".to_string(), counts(&self.backlog_per_tag, usize::MAX)),
+            ("Oldest unseen video".to_string(), oldest_unseen),
+            (
+                format!("No upload for {} months", self.stale_after_months),
+                stale_channels,
+            ),
+        ]
+    }
+}
+
+fn published(video: &Video) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(video.pub_date()).ok()
+}
+
+/// Largest count first, equal counts by name
+fn sorted_counts(counts: HashMap<&str, usize>) -> Vec<Count> {
+    let mut counts: Vec<Count> = counts
+        .into_iter()
+        .map(|(name, count)| Count {
+            name: name.to_string(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB: &str = r#"{"version": 2, "channels": [
+        {"name": "Busy", "id": "busy", "videos": [
+            {"title": "Old", "link": "https://example.com/old", "origin_url": "", "origin_channel_name": "Busy",
+             "marked": false, "pubDate": "2020-01-01T10:00:00+00:00"},
+            {"title": "Seen", "link": "https://example.com/seen", "origin_url": "", "origin_channel_name": "Busy",
+             "marked": true, "pubDate": "2099-01-01T10:00:00+00:00"}
+        ]},
+        {"name": "Quiet", "id": "quiet", "videos": [
+            {"title": "Last", "link": "https://example.com/last", "origin_url": "", "origin_channel_name": "Quiet",
+             "marked": false, "pubDate": "2021-06-01T10:00:00+00:00"}
+        ]},
+        {"name": "Empty", "id": "empty", "videos": []}
+    ]}"#;
+
+    #[test]
+    fn collect() {
+        let channel_list = ChannelList::from_json(DB).unwrap();
+        // the history only keeps the latest open of a video
+        let history: Vec<MinimalVideo> = serde_json::from_str(
+            r#"[
+                {"title": "Seen", "channel": "Feed name", "channel_id": "busy", "opened": 1700000000},
+                {"title": "Gone", "channel": "Removed", "channel_id": "removed"}
+            ]"#,
+        )
+        .unwrap();
+        let open_counts: OpenCounts = serde_json::from_str(
+            r#"{
+                "per_day": {"2023-11-12": 3, "2023-11-13": 1, "2023-11-14": 1},
+                "per_channel": {"busy": 3, "removed": 1, "unknown": 1}
+            }"#,
+        )
+        .unwrap();
+
+        let stats = Stats::collect(&channel_list, &open_counts, &history, 6);
+
+        // names are taken from the channel list if the channel still exists
+        assert_eq!(
+            stats.top_channels,
+            [
+                Count { name: "Busy".to_string(), count: 3 },
+                Count { name: "Removed".to_string(), count: 1 },
+                Count { name: "unknown".to_string(), count: 1 },
+            ]
+        );
+        assert_eq!(stats.watched_per_day.values().sum::<usize>(), 5);
+        // 2023-11-12 is a Sunday
+        assert_eq!(
+            stats.watched_per_week,
+            BTreeMap::from([("2023-W45".to_string(), 3), ("2023-W46".to_string(), 2)])
+        );
+
+        assert_eq!(stats.backlog_per_channel.len(), 2);
+        assert_eq!(stats.oldest_unseen.unwrap().title, "Old");
+
+        let stale: Vec<&str> = stats.stale_channels.iter().map(|channel| channel.name.as_str()).collect();
+        assert_eq!(stale, ["Empty", "Quiet"]);
+    }
+}
@@ -1,5 +1,8 @@
-use crate::backend::data::{feed::*, video::Video};
-use chrono::DateTime;
+use crate::backend::data::{feed::{date::parse_date, *}, video::{Video, VideoKind}};
+use chrono::{DateTime, Local};
+
+/// Videos up to this length in seconds are taken as shorts
+const SHORT_MAX_DURATION: u64 = 60;
 
 #[derive(Default, Clone)]
 pub struct VideoBuilder {
@@ -14,6 +17,17 @@ impl VideoBuilder {
         video_factory
     }
 
+    /// Entry for a stream that is running right now, `link` opens the stream
+    pub fn live(name: &str, link: String) -> Self {
+        let mut video_factory = Self::create();
+        video_factory.video.title = format!("{} is live", name);
+        video_factory.video.link = link;
+        video_factory.video.pub_date = Local::now().to_rfc3339();
+        video_factory.video.kind = VideoKind::Live;
+
+        video_factory
+    }
+
     pub fn get_id(&self) -> &Option<String> {
         &self.video.id
     }
@@ -22,6 +36,10 @@ impl VideoBuilder {
         self.video.id = Some(id);
     }
 
+    pub fn video(&self) -> &Video {
+        &self.video
+    }
+
     pub fn get_title(&self) -> &str {
         &self.video.title
     }
@@ -38,6 +56,26 @@ impl VideoBuilder {
         self.video.link = link;
     }
 
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.video.description = description;
+    }
+
+    pub fn set_thumbnail(&mut self, thumbnail: Option<String>) {
+        self.video.thumbnail = thumbnail;
+    }
+
+    pub fn set_duration(&mut self, duration: Option<u64>) {
+        self.video.duration = duration;
+    }
+
+    pub fn set_views(&mut self, views: Option<u64>) {
+        self.video.views = views;
+    }
+
+    pub fn set_rating(&mut self, rating: Option<f32>) {
+        self.video.rating = rating;
+    }
+
     pub fn set_origin_url(&mut self, url: &str) {
         self.video.origin_url = url.to_string();
     }
@@ -46,8 +84,45 @@ impl VideoBuilder {
         self.video.origin_channel_name = name.to_string();
     }
 
-    pub fn set_pub_date(&mut self, date: String) {
-        self.video.pub_date = date;
+    /// Parse `date` as given by the feed; if that fails, the current time is used and the
+    /// video is flagged accordingly
+    pub fn set_pub_date_from_feed(&mut self, date: &str) {
+        match parse_date(date) {
+            Some(date) => self.video.pub_date = date.to_rfc3339(),
+            None => {
+                self.video.pub_date = Local::now().to_rfc3339();
+                self.video.date_estimated = true;
+            }
+        }
+    }
+
+    pub fn get_kind(&self) -> VideoKind {
+        self.video.kind
+    }
+
+    pub fn set_kind(&mut self, kind: VideoKind) {
+        self.video.kind = kind;
+    }
+
+    /// Shorts are recognized by their link or duration; upcoming streams and premieres
+    /// by a publishing date in the future. Missing views are no signal, fresh uploads
+    /// have none either.
+    fn classify(&mut self) {
+        let video = &mut self.video;
+        if video.kind != VideoKind::Regular {
+            return;
+        }
+
+        let short_duration = video.duration.is_some_and(|d| d > 0 && d <= SHORT_MAX_DURATION);
+        if video.link.contains("/shorts/") || short_duration {
+            video.kind = VideoKind::Short;
+        } else if !video.date_estimated && is_future(&video.pub_date) {
+            video.kind = VideoKind::Upcoming;
+        }
+    }
+
+    pub fn is_date_estimated(&self) -> bool {
+        self.video.date_estimated
     }
 
     pub fn build(self) -> Video {
@@ -55,6 +130,10 @@ impl VideoBuilder {
     }
 }
 
+fn is_future(date: &str) -> bool {
+    DateTime::parse_from_rfc3339(date).is_ok_and(|date| date.timestamp() > Local::now().timestamp())
+}
+
 impl PartialEq<VideoBuilder> for VideoBuilder {
     fn eq(&self, other: &VideoBuilder) -> bool {
         self.video == other.video
@@ -65,16 +144,14 @@ impl From<rss::Video> for VideoBuilder {
     fn from(rss_video: rss::Video) -> Self {
         let mut vf = VideoBuilder::create();
 
-        let title = rss_video.title;
-        let link = rss_video.link;
-        let pub_date = match DateTime::parse_from_rfc2822(&rss_video.pub_date) {
-            Ok(date) => date.to_rfc3339(),
-            Err(e) => panic!("error parsing date in video {}: {}", title, e),
-        };
-
-        vf.set_title(title);
-        vf.set_link(link);
-        vf.set_pub_date(pub_date);
+        vf.set_title(rss_video.title);
+        vf.set_link(rss_video.link);
+        vf.set_pub_date_from_feed(&rss_video.pub_date);
+        vf.set_description(rss_video.description);
+        vf.set_thumbnail(rss_video.thumbnail);
+        vf.set_duration(rss_video.duration);
+        vf.set_views(rss_video.views);
+        vf.classify();
 
         vf
     }
@@ -84,14 +161,44 @@ impl From<atom::Video> for VideoBuilder {
     fn from(atom_vid: atom::Video) -> Self {
         let mut vf = VideoBuilder::create();
 
-        let title = atom_vid.title;
-        let link = format!("https://www.youtube.com/watch?v={}", atom_vid.id);
-        let pub_date = atom_vid.pub_date;
+        vf.set_link(atom_vid.link().unwrap_or_default());
+        if atom_vid.is_short() {
+            vf.set_kind(VideoKind::Short);
+        }
+        vf.set_pub_date_from_feed(atom_vid.date());
+        vf.set_description(atom_vid.description());
+        vf.set_thumbnail(atom_vid.thumbnail());
+        vf.set_views(atom_vid.views());
+        vf.set_rating(atom_vid.rating());
+        vf.set_title(atom_vid.title);
+
+        // youtube video ids are needed for dearrow
+        if let Some(id) = atom_vid.video_id {
+            vf.set_id(id);
+        }
+        vf.classify();
+
+        vf
+    }
+}
+
+impl From<json::Video> for VideoBuilder {
+    fn from(json_video: json::Video) -> Self {
+        let mut vf = VideoBuilder::create();
 
-        vf.set_title(title);
-        vf.set_id(atom_vid.id);
-        vf.set_link(link);
-        vf.set_pub_date(pub_date);
+        let date = json_video
+            .date_published
+            .as_deref()
+            .or(json_video.date_modified.as_deref())
+            .unwrap_or_default();
+
+        vf.set_title(json_video.display_title());
+        vf.set_link(json_video.link().unwrap_or_default());
+        vf.set_pub_date_from_feed(date);
+        vf.set_description(json_video.description());
+        vf.set_duration(json_video.duration());
+        vf.set_thumbnail(json_video.image);
+        vf.classify();
 
         vf
     }
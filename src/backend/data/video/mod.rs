@@ -1,7 +1,7 @@
 pub(super) mod builder;
 
 use crate::backend::ToTuiListItem;
-use chrono::DateTime;
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use tui::{
@@ -19,7 +19,38 @@ pub enum DownloadState {
     Downloaded,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum VideoKind {
+    #[default]
+    Regular,
+    /// stream that is running right now; removed once it ended
+    Live,
+    Short,
+    /// scheduled stream or premiere that can not be watched yet
+    Upcoming,
+}
+
+impl VideoKind {
+    pub fn is_hidden(&self, hide_shorts: bool, hide_upcoming: bool) -> bool {
+        match self {
+            VideoKind::Short => hide_shorts,
+            VideoKind::Upcoming => hide_upcoming,
+            VideoKind::Regular | VideoKind::Live => false,
+        }
+    }
+
+    /// shown in front of the title
+    pub fn marker(&self) -> &'static str {
+        match self {
+            VideoKind::Regular => "",
+            VideoKind::Live => "[LIVE] ",
+            VideoKind::Short => "[SHORT] ",
+            VideoKind::Upcoming => "[UPCOMING] ",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Video {
     pub(super) title: String,
     pub(super) dearrow_title: Option<String>,
@@ -32,6 +63,9 @@ pub struct Video {
 
     #[serde(rename = "pubDate")]
     pub(super) pub_date: String,
+    /// feed date could not be parsed, `pub_date` is the time the video was fetched
+    #[serde(default)]
+    pub(super) date_estimated: bool,
 
     #[serde(skip)]
     pub(super) is_new: bool,
@@ -40,11 +74,36 @@ pub struct Video {
 
     #[serde(default)]
     pub download_state: DownloadState,
+
+    #[serde(default)]
+    pub(super) kind: VideoKind,
+
+    #[serde(default)]
+    pub(super) description: Option<String>,
+    #[serde(default)]
+    pub(super) thumbnail: Option<String>,
+    /// in seconds
+    #[serde(default)]
+    pub(super) duration: Option<u64>,
+    #[serde(default)]
+    pub(super) views: Option<u64>,
+    /// average star rating, 1 to 5
+    #[serde(default)]
+    pub(super) rating: Option<f32>,
+    /// seconds the player stayed open the last time, not the position in the video
+    #[serde(default, alias = "watch_position")]
+    pub(super) open_duration: Option<u64>,
+    /// unix time of the last change of `marked`, `fav` or `open_duration`; 0 if unknown
+    #[serde(default)]
+    pub(super) modified: i64,
 }
 
 impl Video {
     pub fn mark(&mut self, value: bool) {
-        self.marked = value;
+        if self.marked != value {
+            self.marked = value;
+            self.touch();
+        }
     }
 
     pub fn is_fav(&self) -> bool {
@@ -52,17 +111,58 @@ impl Video {
     }
 
     pub fn set_fav(&mut self, is_fav: bool) {
-        self.fav = is_fav;
+        if self.fav != is_fav {
+            self.fav = is_fav;
+            self.touch();
+        }
+    }
+
+    /// Seconds the player stayed open the last time
+    pub(crate) fn set_open_duration(&mut self, seconds: u64) {
+        if self.open_duration != Some(seconds) {
+            self.open_duration = Some(seconds);
+            self.touch();
+        }
+    }
+
+    fn touch(&mut self) {
+        self.modified = chrono::Utc::now().timestamp();
+    }
+
+    /// Identifies the video across machines: its id, or the link if it has none
+    pub(crate) fn state_key(&self) -> &String {
+        self.id.as_ref().unwrap_or(&self.link)
+    }
+
+    pub(crate) fn modified(&self) -> i64 {
+        self.modified
+    }
+
+    /// Take over the watched state synced from another machine
+    pub(crate) fn set_state(&mut self, marked: bool, fav: bool, open_duration: Option<u64>, modified: i64) {
+        self.marked = marked;
+        self.fav = fav;
+        self.open_duration = open_duration;
+        self.modified = modified;
     }
 
     pub fn title(&self) -> &String {
         &self.title
     }
 
+    pub fn dearrow_title(&self) -> Option<&String> {
+        self.dearrow_title.as_ref()
+    }
+
     pub fn link(&self) -> &String {
         &self.link
     }
 
+    #[cfg(feature = "sqlite")]
+    pub fn id(&self) -> Option<&String> {
+        self.id.as_ref()
+    }
+
     pub fn origin_url(&self) -> &String {
         &self.origin_url
     }
@@ -79,6 +179,18 @@ impl Video {
         &self.pub_date
     }
 
+    pub fn kind(&self) -> VideoKind {
+        self.kind
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.kind == VideoKind::Live
+    }
+
+    pub fn is_date_estimated(&self) -> bool {
+        self.date_estimated
+    }
+
     pub fn is_new(&self) -> bool {
         self.is_new
     }
@@ -86,6 +198,85 @@ impl Video {
     pub fn get_details(&self) -> String {
         self.title.to_string()
     }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    pub fn thumbnail(&self) -> Option<&String> {
+        self.thumbnail.as_ref()
+    }
+
+    pub fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+
+    pub fn views(&self) -> Option<u64> {
+        self.views
+    }
+
+    pub fn rating(&self) -> Option<f32> {
+        self.rating
+    }
+
+    pub fn open_duration(&self) -> Option<u64> {
+        self.open_duration
+    }
+
+    /// Duration as `h:mm:ss` or `m:ss`
+    pub fn duration_string(&self) -> Option<String> {
+        Some(format_seconds(self.duration()?))
+    }
+
+    /// Days since the video was published
+    pub(crate) fn age_in_days(&self) -> Option<i64> {
+        let date = DateTime::parse_from_rfc3339(&self.pub_date).ok()?;
+        Some(Local::now().signed_duration_since(date).num_days())
+    }
+
+    /// How long ago the video was published, e.g. `3 days ago`
+    pub fn age_string(&self) -> Option<String> {
+        let date = DateTime::parse_from_rfc3339(&self.pub_date).ok()?;
+        let age = Local::now().signed_duration_since(date);
+
+        let (value, unit) = if age.num_days() >= 365 {
+            (age.num_days() / 365, "year")
+        } else if age.num_days() >= 30 {
+            (age.num_days() / 30, "month")
+        } else if age.num_days() >= 1 {
+            (age.num_days(), "day")
+        } else if age.num_hours() >= 1 {
+            (age.num_hours(), "hour")
+        } else {
+            (age.num_minutes().max(0), "minute")
+        };
+
+        let plural = if value == 1 { "" } else { "s" };
+        Some(format!("{} {}{} ago", value, unit, plural))
+    }
+
+    /// Take everything the feed may have changed from a newer version of this video
+    pub(crate) fn update_from(&mut self, other: Video) {
+        self.title = other.title;
+        self.dearrow_title = other.dearrow_title;
+        self.description = other.description.or(self.description.take());
+        self.thumbnail = other.thumbnail.or(self.thumbnail.take());
+        self.duration = other.duration.or(self.duration);
+        self.views = other.views.or(self.views);
+        self.rating = other.rating.or(self.rating);
+        self.kind = other.kind;
+    }
+}
+
+/// Seconds as `h:mm:ss` or `m:ss`
+pub fn format_seconds(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
 }
 
 impl Ord for Video {
@@ -125,6 +316,8 @@ impl PartialEq<Video> for Video {
     }
 }
 
+impl Eq for Video {}
+
 impl ToTuiListItem for Video {
     fn to_list_item(&self) -> ListItem {
         let new = if self.is_fav() {
@@ -138,13 +331,20 @@ impl ToTuiListItem for Video {
             Some(t) => (t.clone(), " (d)"),
             None => (self.title.clone(), ""),
         };
+        let estimated = if self.date_estimated { "~" } else { "" };
         let date = match DateTime::parse_from_rfc3339(&self.pub_date) {
-            Ok(date_) => format!("{:>4}", &date_.format("%d.%m.%y")),
+            Ok(date_) => format!("{}{:>4}", estimated, &date_.format("%d.%m.%y")),
             Err(_) => String::new(),
         };
 
         let spacer = String::from(" - ");
 
+        let marker = self.kind.marker();
+        let marker_style = match self.kind {
+            VideoKind::Live => Style::default().fg(Color::Red),
+            _ => Style::default().fg(Color::Cyan),
+        };
+
         let yellow = Style::default().fg(Color::Yellow);
         let gray = Style::default()
             .fg(Color::DarkGray)
@@ -153,11 +353,13 @@ impl ToTuiListItem for Video {
         let mut elements = if self.marked {
             vec![
                 Span::styled(new, gray),
+                Span::styled(marker, gray),
                 Span::styled(title, gray),
             ]
         } else {
             vec![
                 Span::styled(new, yellow),
+                Span::styled(marker, marker_style),
                 Span::styled(title, yellow),
             ]
         };
@@ -0,0 +1,214 @@
+//! Watched state of all videos in a file of its own, to sync it between machines
+//! through a shared folder without copying the whole DB.
+
+use crate::backend::{data::{channel_list::ChannelList, video::Video}, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Format of the state file
+const STATE_FILE_VERSION: u64 = 1;
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct StateFile {
+    version: u64,
+    /// keyed by `Video::state_key`, so channel ids do not have to match between machines
+    videos: BTreeMap<String, VideoState>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct VideoState {
+    marked: bool,
+    #[serde(default)]
+    fav: bool,
+    /// seconds the player stayed open the last time; files of older versions call it `position`
+    #[serde(default, alias = "position", skip_serializing_if = "Option::is_none")]
+    open_duration: Option<u64>,
+    /// unix time of the change, 0 if unknown
+    #[serde(default)]
+    modified: i64,
+}
+
+impl VideoState {
+    fn from_video(video: &Video) -> Self {
+        Self {
+            marked: video.marked(),
+            fav: video.is_fav(),
+            open_duration: video.open_duration(),
+            modified: video.modified(),
+        }
+    }
+
+    /// Last writer wins. Changes of unknown age are merged, nothing watched is forgotten.
+    fn merge(&self, other: &Self) -> Self {
+        match self.modified.cmp(&other.modified) {
+            std::cmp::Ordering::Less => other.clone(),
+            std::cmp::Ordering::Greater => self.clone(),
+            std::cmp::Ordering::Equal => Self {
+                marked: self.marked || other.marked,
+                fav: self.fav || other.fav,
+                open_duration: self.open_duration.max(other.open_duration),
+                modified: self.modified,
+            },
+        }
+    }
+
+    /// nothing worth syncing
+    fn is_empty(&self) -> bool {
+        !self.marked && !self.fav && self.open_duration.is_none() && self.modified == 0
+    }
+}
+
+impl StateFile {
+    /// State of all stored videos
+    pub(crate) fn from_channels(channel_list: &ChannelList) -> Self {
+        let mut state = Self {
+            version: STATE_FILE_VERSION,
+            ..Self::default()
+        };
+
+        for video in channel_list.all_videos() {
+            let video_state = VideoState::from_video(video);
+            if !video_state.is_empty() {
+                state.insert(video.state_key().clone(), video_state);
+            }
+        }
+
+        state
+    }
+
+    /// `Err` holds a description of what is wrong
+    pub(crate) fn from_json(json: &str) -> std::result::Result<Self, String> {
+        let state: Self = serde_json::from_str(json).map_err(|error| error.to_string())?;
+
+        if state.version > STATE_FILE_VERSION {
+            return Err(format!(
+                "written by a newer version of tyt (version {}, this build supports up to {})",
+                state.version, STATE_FILE_VERSION
+            ));
+        }
+
+        Ok(state)
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(crate::backend::Error::ParseDB)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.videos.len()
+    }
+
+    fn insert(&mut self, key: String, state: VideoState) {
+        let merged = match self.videos.get(&key) {
+            Some(known) => known.merge(&state),
+            None => state,
+        };
+        self.videos.insert(key, merged);
+    }
+
+    /// Keep the newer state of every video; videos only `other` knows are kept as well
+    pub(crate) fn merge(&mut self, other: StateFile) {
+        for (key, state) in other.videos {
+            self.insert(key, state);
+        }
+    }
+
+    /// Apply newer states to the stored videos. Returns how many videos changed.
+    pub(crate) fn apply(&self, channel_list: &mut ChannelList) -> usize {
+        let mut changed = 0;
+
+        for video in channel_list.all_videos_mut() {
+            let Some(state) = self.videos.get(video.state_key()) else {
+                continue;
+            };
+
+            let local = VideoState::from_video(video);
+            let merged = local.merge(state);
+            if merged != local {
+                video.set_state(merged.marked, merged.fav, merged.open_duration, merged.modified);
+                changed += 1;
+            }
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(marked: bool, modified: i64) -> VideoState {
+        VideoState {
+            marked,
+            fav: false,
+            open_duration: None,
+            modified,
+        }
+    }
+
+    #[test]
+    fn last_writer_wins() {
+        assert_eq!(state(true, 10).merge(&state(false, 20)), state(false, 20));
+        assert_eq!(state(true, 30).merge(&state(false, 20)), state(true, 30));
+    }
+
+    #[test]
+    fn unknown_changes_are_merged() {
+        let watched = VideoState {
+            open_duration: Some(120),
+            ..state(false, 0)
+        };
+        let merged = state(true, 0).merge(&watched);
+
+        assert!(merged.marked);
+        assert_eq!(merged.open_duration, Some(120));
+    }
+
+    #[test]
+    fn older_files_call_it_position() {
+        let state: VideoState = serde_json::from_str(r#"{"marked": true, "position": 90}"#).unwrap();
+        assert_eq!(state.open_duration, Some(90));
+    }
+
+    #[test]
+    fn merge_keeps_videos_of_both_files() {
+        let mut local = StateFile::default();
+        local.insert("a".to_string(), state(true, 10));
+
+        let mut remote = StateFile::default();
+        remote.insert("a".to_string(), state(false, 20));
+        remote.insert("b".to_string(), state(true, 5));
+
+        local.merge(remote);
+        assert_eq!(local.videos["a"], state(false, 20));
+        assert_eq!(local.videos["b"], state(true, 5));
+    }
+
+    #[test]
+    fn apply_to_stored_videos() {
+        let json = include_str!("migration/fixtures/db_2.0.5.json");
+        let mut channel_list = ChannelList::from_json(json).unwrap();
+
+        // keyed by the video id; the first video is marked locally with an unknown time
+        let mut remote = StateFile::default();
+        remote.insert("aaaaaaaaaaa".to_string(), state(false, 20));
+        remote.insert("bbbbbbbbbbb".to_string(), state(false, 0));
+
+        assert_eq!(remote.apply(&mut channel_list), 1);
+        let videos = &channel_list.channels()[0].videos;
+        assert!(!videos[0].marked());
+        assert_eq!(videos[0].modified(), 20);
+        // same age, the local fav is kept
+        assert!(videos[1].is_fav());
+
+        let exported = StateFile::from_channels(&channel_list);
+        assert_eq!(exported.videos.len(), 2);
+        assert_eq!(StateFile::from_json(&exported.to_json().unwrap()).unwrap(), exported);
+    }
+
+    #[test]
+    fn refuses_newer_files() {
+        assert!(StateFile::from_json(r#"{"version":99,"videos":{}}"#).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::backend::http::{execute, HostLimiter, RetryPolicy};
 use reqwest::blocking::Client;
 use serde::Deserialize;
 
@@ -13,10 +14,21 @@ struct ResponseTitles {
     votes: usize,
 }
 
-pub fn get_best_title(video_id: &str) -> Option<String> {
-    let client = Client::builder().build().unwrap();
+/// Title with the most votes; requests are retried and limited like feed downloads
+pub(crate) fn get_best_title(
+    client: &Client,
+    limiter: &HostLimiter,
+    policy: &RetryPolicy,
+    video_id: &str,
+) -> Option<String> {
     let url = format!("https://sponsor.ajay.app/api/branding/?videoID={}", video_id);
-    let resp: Response = client.get(url).send().ok()?.json().ok()?;
+    let resp: Response = client
+        .get(url)
+        .build()
+        .and_then(|req| execute(client, req, limiter, policy))
+        .ok()?
+        .json()
+        .ok()?;
 
     let first = resp.titles.first()?;
 
@@ -1,20 +1,25 @@
 use crate::backend::{
-    core::Core,
-    Screen,
+    core::{Core, FetchState},
+    io::current_profile,
+    data::{channel::Channel, stats::Stats, video::{format_seconds, DownloadState, Video}},
+    DetailPanePosition, Screen,
     Screen::*,
 };
+use chrono::DateTime;
 use std::{thread, rc::Rc, sync::{RwLock, Arc}};
 use tui::widgets::ListItem;
 use tui::{
     layout::{Alignment, Constraint::*, Direction, Layout, Rect},
-    style::Style,
-    text::Span,
-    widgets::{Block, BorderType, Borders, List, Paragraph},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, Paragraph, Wrap},
     Frame,
 };
 
 const INFO_LINE: &str =
-    "q close; o open video/select; Enter/l select; Esc/h go back; m mark; M unmark";
+    "q close; o open video/select; Enter/l select; Esc/h go back; m mark; M unmark; i details; J/K scroll details; P profiles; H history; S statistics";
+const HISTORY_INFO_LINE: &str =
+    "Esc/h/H go back; Enter/l go to video; o open again; d remove entry; D clear history";
 
 #[derive(Default)]
 struct Widget<'a> {
@@ -59,12 +64,14 @@ impl<'a> Widget<'a> {
 pub struct AppLayout {
     main: Rc<[Rect]>,
     content: Rc<[Rect]>,
+    videos: Rc<[Rect]>,
 }
 
 impl AppLayout {
-    fn load(f: &mut Frame<'_>, screen: &Screen) -> Self {
+    /// `details` is the position of the video detail pane, if it is shown
+    fn load(f: &mut Frame<'_>, screen: &Screen, details: Option<DetailPanePosition>) -> Self {
         let video_size = match screen {
-            Channels => 0,
+            Channels | PlaybackHistory => 0,
             Videos => 75,
         };
 
@@ -83,9 +90,22 @@ impl AppLayout {
             .constraints(content_split)
             .split(main[0]);
 
+        let (direction, details_size) = match details {
+            Some(DetailPanePosition::Right) if *screen == Videos => (Direction::Horizontal, 45),
+            Some(DetailPanePosition::Bottom) if *screen == Videos => (Direction::Vertical, 50),
+            _ => (Direction::Horizontal, 0),
+        };
+
+        let videos = Layout::default()
+            .direction(direction)
+            .margin(0)
+            .constraints(vec![Percentage(100 - details_size), Percentage(details_size)])
+            .split(content[1]);
+
         Self {
             main,
             content,
+            videos,
         }
     }
 
@@ -102,8 +122,170 @@ impl AppLayout {
     }
 
     fn videos(&self) -> Rect {
-        self.content[1]
+        self.videos[0]
+    }
+
+    fn video_details(&self) -> Rect {
+        self.videos[1]
+    }
+
+    /// centered area covering most of the channel list
+    fn popup(&self) -> Rect {
+        let area = self.main[0];
+        let width = area.width * 3 / 4;
+        let height = area.height * 3 / 4;
+
+        Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+/// Fetch state of a channel, listing every url that failed
+fn details_popup(channel: &Channel) -> Paragraph<'static> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let red = Style::default().fg(Color::Red);
+    let yellow = Style::default().fg(Color::Yellow);
+
+    let mut lines = vec![
+        Line::from(Span::styled(channel.name().clone(), yellow)),
+        Line::from(Span::styled(channel.id().clone(), gray)),
+        Line::from(""),
+        Line::from(format!("State: {}", channel.fetch_state)),
+    ];
+
+    match &channel.fetch_state {
+        FetchState::DownloadsFailure(errors) => {
+            for (url, error) in errors.iter() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(url.clone(), gray)));
+                lines.push(Line::from(Span::styled(error.to_string(), red)));
+            }
+        }
+        FetchState::FetchedWithWarnings(warnings) => {
+            lines.push(Line::from(""));
+            for warning in warnings.iter() {
+                lines.push(Line::from(Span::styled(warning.clone(), yellow)));
+            }
+        }
+        _ => {}
+    }
+
+    let block = Block::default()
+        .title(" Details ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+
+    Paragraph::new(lines).block(block).wrap(Wrap { trim: false })
+}
+
+/// Everything known about a video; the description can be scrolled with `scroll`
+fn video_details(video: &Video, scroll: u16) -> Paragraph<'static> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let yellow = Style::default().fg(Color::Yellow);
+
+    let mut lines = Vec::new();
+
+    match video.dearrow_title() {
+        Some(title) => {
+            lines.push(Line::from(Span::styled(title.clone(), yellow)));
+            lines.push(Line::from(Span::styled(format!("Original: {}", video.title()), gray)));
+        }
+        None => lines.push(Line::from(Span::styled(video.title().clone(), yellow))),
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!("Channel: {}", video.origin_channel_name())));
+
+    let estimated = if video.is_date_estimated() { " (estimated)" } else { "" };
+    let date = match (DateTime::parse_from_rfc3339(video.pub_date()), video.age_string()) {
+        (Ok(date), Some(age)) => {
+            format!("Published: {}, {}{}", date.format("%d.%m.%Y %H:%M"), age, estimated)
+        }
+        _ => format!("Published: {}{}", video.pub_date(), estimated),
+    };
+    lines.push(Line::from(date));
+    lines.push(Line::from(Span::styled(video.link().clone(), gray)));
+
+    let mut facts = Vec::new();
+    if let Some(duration) = video.duration_string() {
+        facts.push(format!("Duration: {}", duration));
+    }
+    if let Some(views) = video.views() {
+        facts.push(format!("Views: {}", views));
     }
+    if let Some(rating) = video.rating() {
+        facts.push(format!("Rating: {:.2}", rating));
+    }
+    if !facts.is_empty() {
+        lines.push(Line::from(facts.join("  ")));
+    }
+
+    let download = match video.download_state {
+        DownloadState::NotDownloaded => "not downloaded",
+        DownloadState::Downloading => "downloading",
+        DownloadState::Downloaded => "downloaded",
+    };
+    lines.push(Line::from(format!("Download: {}", download)));
+
+    // the time the player stayed open, tyt does not know the position in the video
+    let opened = match video.open_duration() {
+        Some(seconds) => format!("player open for {} the last time", format_seconds(seconds)),
+        None if video.marked() => String::from("watched"),
+        None => String::from("not opened"),
+    };
+    lines.push(Line::from(format!("Opened: {}", opened)));
+
+    if let Some(thumbnail) = video.thumbnail() {
+        lines.push(Line::from(Span::styled(format!("Thumbnail: {}", thumbnail), gray)));
+    }
+
+    if let Some(description) = video.description() {
+        lines.push(Line::from(""));
+        lines.extend(description.lines().map(|line| Line::from(line.to_string())));
+    }
+
+    let block = Block::default()
+        .title(" Video ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+}
+
+/// Every section of the statistics with its rows, scrolled by `scroll`
+fn stats_popup(stats: &Stats, scroll: u16) -> Paragraph<'static> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let yellow = Style::default().fg(Color::Yellow);
+
+    let mut lines = Vec::new();
+    for (heading, rows) in stats.report() {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(heading, yellow)));
+
+        if rows.is_empty() {
+            lines.push(Line::from(Span::styled("  none", gray)));
+        }
+        lines.extend(rows.into_iter().map(|row| Line::from(format!("  {}", row))));
+    }
+
+    let block = Block::default()
+        .title(" Statistics ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
 }
 
 #[allow(clippy::unnecessary_unwrap)]
@@ -121,6 +303,7 @@ pub fn draw(core: Arc<RwLock<Core>>) {
             app_title,
             terminal,
             history,
+            status_message,
         ) = {
             // let core_read_lock = core.try_read().unwrap();
             (
@@ -129,20 +312,30 @@ pub fn draw(core: Arc<RwLock<Core>>) {
                 core_lock.config.app_title.clone(),
                 core_lock.terminal.term.clone(),
                 core_lock.playback_history.clone(),
+                core_lock.status_message().cloned(),
             )
         };
 
         let channel_symbol = match current_screen {
             Channels => ">> ",
-            Videos => "-",
+            Videos | PlaybackHistory => "-",
+        };
+        let title = match current_profile() {
+            Some(profile) => format!(" {} [{}] ", app_title, profile),
+            None => format!(" {} ", app_title),
         };
         let chan_widget = Widget::builder()
-            .with_title(&format!(" {} ", app_title))
+            .with_title(&title)
             .with_symbol(channel_symbol)
             .with_list(channels.get_spans_list());
 
         let _ = terminal.lock().unwrap().draw(|f| {
-            let layout = AppLayout::load(f, &current_screen);
+            let details = if core_lock.show_video_details {
+                Some(core_lock.config.detail_pane_position)
+            } else {
+                None
+            };
+            let layout = AppLayout::load(f, &current_screen, details);
 
             f.render_stateful_widget(
                 chan_widget.render(),
@@ -164,17 +357,62 @@ pub fn draw(core: Arc<RwLock<Core>>) {
                 );
             }
 
+            if current_screen == Videos && core_lock.show_video_details {
+                let scroll = core_lock.details_scroll;
+                if let Some(video) = core_lock.get_selected_video() {
+                    f.render_widget(video_details(video, scroll), layout.video_details());
+                }
+            }
+
+            if current_screen == Channels && core_lock.show_details {
+                if let Some(popup) = core_lock.get_selected_channel().map(details_popup) {
+                    f.render_widget(Clear, layout.popup());
+                    f.render_widget(popup, layout.popup());
+                }
+            }
+
+            if let Some(switcher) = core_lock.profile_switcher.as_mut() {
+                let switcher_widget = Widget::builder()
+                    .with_title(" Profiles ")
+                    .with_symbol(">> ")
+                    .with_list(switcher.to_list_items());
+
+                f.render_widget(Clear, layout.popup());
+                f.render_stateful_widget(switcher_widget.render(), layout.popup(), switcher.state_mut());
+            }
+
+            if let Some(stats) = core_lock.stats.as_ref() {
+                f.render_widget(Clear, layout.popup());
+                f.render_widget(stats_popup(stats, core_lock.details_scroll), layout.popup());
+            }
+
+            let history_symbol = match current_screen {
+                PlaybackHistory => ">> ",
+                Channels | Videos => "",
+            };
             let history_widget = Widget::builder()
                 .with_title(" Playback History ")
+                .with_symbol(history_symbol)
                 .with_list(history.to_list_items());
 
-            f.render_widget(history_widget.render(), layout.history());
+            f.render_stateful_widget(
+                history_widget.render(),
+                layout.history(),
+                core_lock.playback_history.state_mut(),
+            );
 
             //////////////////////////////
 
-            let info = Paragraph::new(Span::from(INFO_LINE))
-                .style(Style::default())
-                .alignment(Alignment::Left);
+            // errors replace the key help until they time out
+            let info = match status_message {
+                Some(message) => Paragraph::new(Span::from(message))
+                    .style(Style::default().fg(Color::Red)),
+                None if current_screen == PlaybackHistory => {
+                    Paragraph::new(Span::from(HISTORY_INFO_LINE)).style(Style::default())
+                }
+                None => Paragraph::new(Span::from(INFO_LINE)).style(Style::default()),
+            }
+            .alignment(Alignment::Left);
 
             f.render_widget(info, layout.info());
         });
@@ -0,0 +1,334 @@
+use crate::backend::{
+    io::config::Config,
+    Error::{HttpClient, InvalidHeader},
+    Result,
+};
+use chrono::{DateTime, Utc};
+use log::*;
+use serde::de::DeserializeOwned;
+use reqwest::{
+    blocking::{Client, Request, Response},
+    header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+    redirect::Policy,
+    Proxy, StatusCode,
+};
+use std::{
+    cmp::{max, min},
+    collections::HashMap,
+    sync::{Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Upper bound for a single wait between two attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(120);
+
+/// Build the client used for all network requests, configured from `Config`.
+/// The client is cheap to clone; clones share the same connection pool.
+pub(crate) fn build_client(config: &Config) -> Result<Client> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in config.extra_headers.iter() {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| InvalidHeader(name.clone()))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| InvalidHeader(name.to_string()))?;
+        headers.insert(name, value);
+    }
+
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(config.connect_timeout))
+        .timeout(Duration::from_secs(config.request_timeout))
+        .user_agent(&config.user_agent)
+        .default_headers(headers)
+        .redirect(Policy::limited(config.max_redirects));
+
+    // socks5:// and socks5h:// urls are supported as well, e.g. for tor
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(Proxy::all(proxy).map_err(HttpClient)?);
+    }
+
+    builder.build().map_err(HttpClient)
+}
+
+/// Retry settings for transient request failures
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RetryPolicy {
+    retries: usize,
+    backoff: Duration,
+}
+
+impl From<&Config> for RetryPolicy {
+    fn from(config: &Config) -> Self {
+        Self {
+            retries: config.fetch_retries,
+            backoff: Duration::from_millis(config.retry_backoff),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// exponential backoff, doubled with every attempt
+    fn delay(&self, attempt: usize) -> Duration {
+        let factor = 1u32 << min(attempt, 16);
+        min(self.backoff.saturating_mul(factor), MAX_RETRY_DELAY)
+    }
+
+    /// delay before retrying a transient response; `Retry-After` takes precedence
+    fn response_delay(&self, headers: &HeaderMap, attempt: usize) -> Duration {
+        retry_after(headers).unwrap_or_else(|| self.delay(attempt))
+    }
+}
+
+/// Limits how many requests run at the same time against one host,
+/// and how fast new requests to that host are started
+pub(crate) struct HostLimiter {
+    max_active: usize,
+    interval: Duration,
+    hosts: Mutex<HashMap<String, HostState>>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct HostState {
+    active: usize,
+    last_start: Option<Instant>,
+}
+
+/// Slot for one request; released when dropped
+pub(crate) struct HostPermit<'a> {
+    limiter: &'a HostLimiter,
+    host: String,
+}
+
+impl From<&Config> for HostLimiter {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_active: max(config.max_requests_per_host, 1),
+            interval: Duration::from_millis(config.host_request_interval),
+            hosts: Mutex::new(HashMap::new()),
+            changed: Condvar::new(),
+        }
+    }
+}
+
+impl HostLimiter {
+    /// Block until a request to `host` may be started
+    pub(crate) fn acquire(&self, host: &str) -> HostPermit<'_> {
+        let mut hosts = self.hosts.lock().unwrap();
+
+        loop {
+            let state = hosts.entry(host.to_string()).or_default();
+            let now = Instant::now();
+            let wait = state
+                .last_start
+                .map(|last| (last + self.interval).saturating_duration_since(now))
+                .unwrap_or_default();
+
+            if state.active < self.max_active && wait.is_zero() {
+                state.active += 1;
+                state.last_start = Some(now);
+                break;
+            }
+
+            hosts = if wait.is_zero() {
+                self.changed.wait(hosts).unwrap()
+            } else {
+                self.changed.wait_timeout(hosts, wait).unwrap().0
+            };
+        }
+
+        HostPermit {
+            limiter: self,
+            host: host.to_string(),
+        }
+    }
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        let mut hosts = self.limiter.hosts.lock().unwrap();
+        if let Some(state) = hosts.get_mut(&self.host) {
+            state.active = state.active.saturating_sub(1);
+        }
+        self.limiter.changed.notify_all();
+    }
+}
+
+/// Response of [`execute`]; the request counts against the limit of its host until the
+/// body was read
+pub(crate) struct LimitedResponse<'a> {
+    response: Response,
+    _permit: HostPermit<'a>,
+}
+
+impl LimitedResponse<'_> {
+    pub(crate) fn status(&self) -> StatusCode {
+        self.response.status()
+    }
+
+    pub(crate) fn headers(&self) -> &HeaderMap {
+        self.response.headers()
+    }
+
+    pub(crate) fn text(self) -> reqwest::Result<String> {
+        self.response.text()
+    }
+
+    pub(crate) fn json<T: DeserializeOwned>(self) -> reqwest::Result<T> {
+        self.response.json()
+    }
+}
+
+/// Send `request`, retrying timeouts, connection errors, 5xx and 429 responses.
+/// A `Retry-After` header takes precedence over the backoff of `policy`.
+pub(crate) fn execute<'a>(
+    client: &Client,
+    request: Request,
+    limiter: &'a HostLimiter,
+    policy: &RetryPolicy,
+) -> reqwest::Result<LimitedResponse<'a>> {
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    let mut attempt = 0;
+
+    loop {
+        // requests with streaming bodies can not be retried
+        let retry = match request.try_clone() {
+            Some(retry) if attempt < policy.retries => retry,
+            _ => {
+                let permit = limiter.acquire(&host);
+                return client.execute(request).map(|response| LimitedResponse {
+                    response,
+                    _permit: permit,
+                });
+            }
+        };
+
+        let permit = limiter.acquire(&host);
+        let result = client.execute(retry);
+
+        let delay = match &result {
+            Ok(res) if is_transient_status(res.status()) => policy.response_delay(res.headers(), attempt),
+            Err(error) if error.is_timeout() || error.is_connect() => policy.delay(attempt),
+            _ => {
+                return result.map(|response| LimitedResponse {
+                    response,
+                    _permit: permit,
+                })
+            }
+        };
+        drop(permit);
+
+        debug!("Retrying {} in {:?} (attempt {})", request.url(), delay, attempt + 1);
+        thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// parse `Retry-After`, given either in seconds or as http date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?;
+
+    let delay = match value.trim().parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(value).ok()?;
+            (date.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or_default()
+        }
+    };
+
+    Some(min(delay, MAX_RETRY_DELAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            retries: 3,
+            backoff: Duration::from_millis(500),
+        }
+    }
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = policy();
+        assert_eq!(policy.delay(0), Duration::from_millis(500));
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+        assert_eq!(policy.delay(8), MAX_RETRY_DELAY);
+        // the shift is bounded, large attempts do not overflow
+        assert_eq!(policy.delay(usize::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn retry_after_seconds_or_date() {
+        let policy = policy();
+        assert_eq!(policy.response_delay(&headers("7"), 0), Duration::from_secs(7));
+        assert_eq!(policy.response_delay(&headers(" 7 "), 0), Duration::from_secs(7));
+        assert_eq!(policy.response_delay(&headers("3600"), 0), MAX_RETRY_DELAY);
+
+        let date = (Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        let delay = policy.response_delay(&headers(&date), 0);
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30), "{:?}", delay);
+
+        // dates in the past mean right away
+        assert_eq!(
+            policy.response_delay(&headers("Wed, 21 Oct 2015 07:28:00 GMT"), 0),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn permit_is_held_until_the_body_was_read() {
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nfeed")
+                .unwrap();
+        });
+
+        let limiter = HostLimiter {
+            max_active: 1,
+            interval: Duration::ZERO,
+            hosts: Mutex::new(HashMap::new()),
+            changed: Condvar::new(),
+        };
+        let active = || limiter.hosts.lock().unwrap().get("127.0.0.1").map_or(0, |state| state.active);
+
+        let client = Client::new();
+        let request = client.get(&url).build().unwrap();
+        let response = execute(&client, request, &limiter, &policy()).unwrap();
+        assert_eq!(active(), 1);
+
+        assert_eq!(response.text().unwrap(), "feed");
+        assert_eq!(active(), 0);
+    }
+
+    #[test]
+    fn invalid_retry_after_falls_back_to_backoff() {
+        let policy = policy();
+        assert_eq!(policy.response_delay(&HeaderMap::new(), 1), Duration::from_secs(1));
+        assert_eq!(policy.response_delay(&headers("soon"), 1), Duration::from_secs(1));
+        assert_eq!(policy.response_delay(&headers("-5"), 2), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::backend::{
+    data::video::Video,
+    io::{read_config, write_config, FileType::ArchiveFile},
+    Result,
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// Videos removed by the retention settings. They count as seen, so they are not added
+/// again as new videos when a feed still lists them.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Archive {
+    /// `Video::state_key` and when the video was removed or last listed by its feed (unix time)
+    videos: BTreeMap<String, i64>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl Archive {
+    pub(crate) fn load() -> Self {
+        serde_json::from_str(&read_config(ArchiveFile)).unwrap_or_default()
+    }
+
+    /// Store the archive if anything changed since it was loaded or stored
+    pub(crate) fn save(&mut self) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+
+        let string = serde_json::to_string(self).unwrap();
+        write_config(ArchiveFile, &string)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Remove archived videos from fetched `videos`. Their entries are kept alive as long
+    /// as the feed lists them, refreshed at most once a day to spare writes.
+    pub(crate) fn remove_archived(&mut self, videos: &mut Vec<Video>) {
+        let now = Local::now().timestamp();
+        let (archive, changed) = (&mut self.videos, &mut self.changed);
+
+        videos.retain(|video| match archive.get_mut(video.state_key()) {
+            Some(seen) => {
+                if now - *seen > DAY {
+                    *seen = now;
+                    *changed = true;
+                }
+                false
+            }
+            None => true,
+        });
+    }
+
+    /// Remember pruned `videos`
+    pub(crate) fn add(&mut self, videos: &[Video]) {
+        let now = Local::now().timestamp();
+        for video in videos {
+            self.videos.insert(video.state_key().clone(), now);
+            self.changed = true;
+        }
+    }
+
+    /// Forget videos no feed listed for `max_age` days; `None` keeps them forever
+    pub(crate) fn expire(&mut self, max_age: Option<i64>) {
+        let Some(max_age) = max_age else {
+            return;
+        };
+
+        let oldest = Local::now().timestamp() - max_age * DAY;
+        let len = self.videos.len();
+        self.videos.retain(|_, seen| *seen >= oldest);
+        self.changed |= self.videos.len() != len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(id: &str) -> Video {
+        serde_json::from_value(serde_json::json!({
+            "title": id,
+            "link": format!("https://example.com/{}", id),
+            "origin_url": "",
+            "origin_channel_name": "",
+            "marked": true,
+            "pubDate": "2024-01-01T00:00:00+00:00",
+            "id": id,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn entries_expire_once_feeds_stop_listing_them() {
+        let now = Local::now().timestamp();
+        let mut archive = Archive {
+            videos: BTreeMap::from([
+                ("listed".to_string(), now - 400 * DAY),
+                ("gone".to_string(), now - 400 * DAY),
+                ("recent".to_string(), now - 10 * DAY),
+            ]),
+            changed: false,
+        };
+
+        // the feed still lists `listed`, it stays archived and is not added again
+        let mut fetched = vec![video("listed"), video("new")];
+        archive.remove_archived(&mut fetched);
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].state_key(), "new");
+        assert!(archive.changed);
+
+        archive.expire(Some(365));
+        let keys: Vec<&str> = archive.videos.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["listed", "recent"]);
+
+        archive.expire(None);
+        assert_eq!(archive.videos.len(), 2);
+    }
+}
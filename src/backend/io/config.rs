@@ -1,9 +1,11 @@
 use crate::backend::{
+    data::filter::{Filter, FilterRules},
     io::{read_config, FileType::ConfigFile},
-    Error::ParseConfig,
-    Result, SortingMethodVideos, SortingMethodChannels,
+    Error::{InvalidRegex, ParseConfig},
+    DetailPanePosition, Result, Storage, StoredVideosFilter, SortingMethodVideos, SortingMethodChannels,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 const SCHOW_EMPTY_CHANNEL_DEFAULT: bool = true;
 const MARK_ON_OPEN_DEFAULT: bool = true;
@@ -14,6 +16,23 @@ const MASSAGE_TIMEOUT_DEFAULT: usize = 20;
 const NOTIFY_WITH_DEFAULT: &str = "notify_send";
 const VIDEO_PLAYER_DEFAULT: &str = "mpv";
 const USE_DEARROW_DEFAULT: bool = false;
+const HIDE_SHORTS_DEFAULT: bool = false;
+const HIDE_UPCOMING_DEFAULT: bool = false;
+const STALE_AFTER_MONTHS_DEFAULT: u32 = 6;
+const MAX_HISTORY_DEFAULT: usize = 200;
+const KEEP_FAVOURITES_DEFAULT: bool = true;
+const MAX_ARCHIVE_AGE_DEFAULT: i64 = 180;
+const CONNECT_TIMEOUT_DEFAULT: u64 = 10;
+const REQUEST_TIMEOUT_DEFAULT: u64 = 30;
+const USER_AGENT_DEFAULT: &str = concat!("tyt/", env!("CARGO_PKG_VERSION"));
+const MAX_REDIRECTS_DEFAULT: usize = 10;
+const FETCH_WORKERS_DEFAULT: usize = 10;
+const FETCH_RETRIES_DEFAULT: usize = 3;
+const RETRY_BACKOFF_DEFAULT: u64 = 1000;
+const MAX_REQUESTS_PER_HOST_DEFAULT: usize = 4;
+const HOST_REQUEST_INTERVAL_DEFAULT: u64 = 100;
+const TWITCH_RSS_BRIDGE_DEFAULT: &str = "https://twitchrss.appspot.com/vod/{channel}";
+const TWITCH_LIVE_CHECK_DEFAULT: &str = "https://www.twitch.tv/{channel}";
 /* const DEFAULT_SORT: SortingMethod = SortingMethod::default(); */
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -30,6 +49,42 @@ pub struct Config {
     pub sort_videos: SortingMethodVideos,
     pub notify_with: String,
     pub use_dearrow_titles: bool,
+    pub hide_shorts: bool,
+    pub hide_upcoming: bool,
+    /// applied to the videos of all subscriptions
+    pub filter: FilterRules,
+    pub filter_stored_videos: StoredVideosFilter,
+    pub detail_pane_position: DetailPanePosition,
+    /// only `Json` is available if tyt was built without the `sqlite` feature
+    pub storage: Storage,
+    /// videos per channel that are kept after an update; `None` keeps all
+    pub max_videos_per_channel: Option<usize>,
+    /// days after which marked videos are removed; `None` keeps them
+    pub max_seen_age: Option<i64>,
+    pub keep_favourites: bool,
+    /// days a pruned video is remembered after its feed listed it the last time; `None` keeps it forever
+    pub max_archive_age: Option<i64>,
+    /// entries of the playback history, the oldest are dropped first
+    pub max_history: usize,
+    /// channels without upload for this long are listed in the statistics
+    pub stale_after_months: u32,
+    pub connect_timeout: u64,
+    /// seconds for a whole request, from connecting until the body was read
+    #[serde(alias = "read_timeout")]
+    pub request_timeout: u64,
+    pub proxy: Option<String>,
+    pub user_agent: String,
+    pub extra_headers: BTreeMap<String, String>,
+    pub max_redirects: usize,
+    pub fetch_workers: usize,
+    pub fetch_retries: usize,
+    pub retry_backoff: u64,
+    pub max_requests_per_host: usize,
+    pub host_request_interval: u64,
+    /// `{channel}` is replaced by the name of the twitch channel
+    pub twitch_rss_bridge: String,
+    /// page that is checked for a running stream; `None` disables the check
+    pub twitch_live_check: Option<String>,
 }
 
 impl Default for Config {
@@ -46,6 +101,31 @@ impl Default for Config {
             video_player: VIDEO_PLAYER_DEFAULT.into(),
             sort_videos: SortingMethodVideos::default(),
             use_dearrow_titles: USE_DEARROW_DEFAULT,
+            hide_shorts: HIDE_SHORTS_DEFAULT,
+            hide_upcoming: HIDE_UPCOMING_DEFAULT,
+            filter: FilterRules::default(),
+            filter_stored_videos: StoredVideosFilter::default(),
+            detail_pane_position: DetailPanePosition::default(),
+            storage: Storage::default(),
+            max_videos_per_channel: None,
+            max_seen_age: None,
+            keep_favourites: KEEP_FAVOURITES_DEFAULT,
+            max_archive_age: Some(MAX_ARCHIVE_AGE_DEFAULT),
+            max_history: MAX_HISTORY_DEFAULT,
+            stale_after_months: STALE_AFTER_MONTHS_DEFAULT,
+            connect_timeout: CONNECT_TIMEOUT_DEFAULT,
+            request_timeout: REQUEST_TIMEOUT_DEFAULT,
+            proxy: None,
+            user_agent: USER_AGENT_DEFAULT.into(),
+            extra_headers: BTreeMap::new(),
+            max_redirects: MAX_REDIRECTS_DEFAULT,
+            fetch_workers: FETCH_WORKERS_DEFAULT,
+            fetch_retries: FETCH_RETRIES_DEFAULT,
+            retry_backoff: RETRY_BACKOFF_DEFAULT,
+            max_requests_per_host: MAX_REQUESTS_PER_HOST_DEFAULT,
+            host_request_interval: HOST_REQUEST_INTERVAL_DEFAULT,
+            twitch_rss_bridge: TWITCH_RSS_BRIDGE_DEFAULT.into(),
+            twitch_live_check: Some(TWITCH_LIVE_CHECK_DEFAULT.into()),
         }
     }
 }
@@ -55,9 +135,15 @@ impl Config {
     pub(crate) fn read() -> Result<Self> {
         let config_str = read_config(ConfigFile);
 
-        match serde_yaml::from_str(&config_str) {
-            Ok(config) => Ok(config),
-            Err(error) => Err(ParseConfig(error)),
+        let config: Self = match serde_yaml::from_str(&config_str) {
+            Ok(config) => config,
+            Err(error) => return Err(ParseConfig(error)),
+        };
+
+        if let Err(error) = Filter::new(&[&config.filter], None) {
+            return Err(InvalidRegex("config".to_string(), error));
         }
+
+        Ok(config)
     }
 }
@@ -0,0 +1,140 @@
+use crate::backend::io::{read_config, write_config, FileType::FeedCacheFile};
+use log::*;
+use reqwest::header::{HeaderMap, ETAG, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+/// Validators of the last stored download of each subscription
+#[derive(Clone, Deserialize, Serialize, Default)]
+pub(crate) struct FeedCache {
+    #[serde(default)]
+    subscriptions: HashMap<String, FeedValidators>,
+}
+
+/// Validators of the feed urls of one subscription. They only match the stored videos
+/// as long as the urls and filters of the subscription stay the same.
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub(crate) struct FeedValidators {
+    /// hash of the urls and filters the feeds were downloaded with
+    fingerprint: u64,
+    entries: HashMap<String, CacheEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub(crate) struct CacheEntry {
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+}
+
+impl FeedCache {
+    pub(crate) fn load() -> Self {
+        let cache = read_config(FeedCacheFile);
+        serde_json::from_str(&cache).unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self) {
+        let string = serde_json::to_string(self).unwrap();
+        if let Err(error) = write_config(FeedCacheFile, &string) {
+            warn!("{}", error);
+        }
+    }
+
+    /// Validators of subscription `id` if they were stored with the same `settings`,
+    /// otherwise empty ones for `settings`
+    pub(crate) fn validators(&self, id: &str, settings: &impl Serialize) -> FeedValidators {
+        let fingerprint = fingerprint(settings);
+
+        match self.subscriptions.get(id) {
+            Some(validators) if validators.fingerprint == fingerprint => validators.clone(),
+            _ => FeedValidators {
+                fingerprint,
+                entries: HashMap::new(),
+            },
+        }
+    }
+
+    /// Replace the validators of subscription `id`, once its videos are stored
+    pub(crate) fn set(&mut self, id: &str, validators: FeedValidators) {
+        self.subscriptions.insert(id.to_string(), validators);
+    }
+}
+
+impl FeedValidators {
+    pub(crate) fn get(&self, url: &str) -> Option<&CacheEntry> {
+        self.entries.get(url)
+    }
+
+    /// Forget validators of urls the subscription does not have anymore
+    pub(crate) fn retain_urls(&mut self, urls: &[String]) {
+        self.entries.retain(|url, _| urls.contains(url));
+    }
+
+    /// Forget all validators, so every feed is downloaded and parsed again
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Remember the validators sent with a response. Feeds without any are forgotten.
+    pub(crate) fn update(&mut self, url: &str, headers: &HeaderMap) {
+        let entry = CacheEntry::from(headers);
+
+        if entry.etag.is_none() && entry.last_modified.is_none() {
+            self.entries.remove(url);
+        } else {
+            self.entries.insert(url.to_string(), entry);
+        }
+    }
+}
+
+fn fingerprint(settings: &impl Serialize) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(settings).unwrap().hash(&mut hasher);
+    hasher.finish()
+}
+
+impl From<&HeaderMap> for CacheEntry {
+    fn from(headers: &HeaderMap) -> Self {
+        let value = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+
+        CacheEntry {
+            etag: value(ETAG),
+            last_modified: value(LAST_MODIFIED),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(etag: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_str(etag).unwrap());
+        headers
+    }
+
+    #[test]
+    fn validators_per_subscription_and_settings() {
+        let url = "https://example.com/feed.xml";
+        let mut cache = FeedCache::default();
+
+        let mut validators = cache.validators("first", &(url, "filter"));
+        validators.update(url, &headers("\"v1\""));
+        cache.set("first", validators);
+
+        assert_eq!(cache.validators("first", &(url, "filter")).get(url).unwrap().etag.as_deref(), Some("\"v1\""));
+        // another subscription of the same feed did not store these videos
+        assert!(cache.validators("second", &(url, "filter")).get(url).is_none());
+        // other filters may keep videos that were dropped before
+        assert!(cache.validators("first", &(url, "other filter")).get(url).is_none());
+    }
+}
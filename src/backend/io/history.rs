@@ -1,59 +1,213 @@
 use crate::backend::{
-    data::video::Video,
+    data::video::{format_seconds, Video},
     io::{read_config, write_config, FileType::HistoryFile},
-    ToTuiListItem,
+    Error::ParseDB,
+    Result, Storage, ToTuiListItem,
 };
+#[cfg(feature = "sqlite")]
+use crate::backend::io::sqlite::Database;
+use chrono::{Local, TimeZone};
 use serde::{Deserialize, Serialize};
 use tui::{
     style::{Color, Modifier, Style},
     text::{Span, Line},
-    widgets::ListItem,
+    widgets::{ListItem, ListState},
 };
 
 #[derive(Clone, Deserialize, Serialize, Default)]
 pub(crate) struct History {
     list: Vec<MinimalVideo>,
+    #[serde(skip)]
+    storage: Storage,
+    /// oldest entries are dropped beyond this length
+    #[serde(skip)]
+    max_length: usize,
+    /// selection in the history pane, newest entry first
+    #[serde(skip)]
+    state: ListState,
 }
 
 impl History {
-    pub(crate) fn load() -> Self {
-        let history = read_config(HistoryFile);
-        match serde_json::from_str(&history) {
-            Ok(list) => Self { list },
-            Err(_) => Self::default(),
+    pub(crate) fn load(storage: Storage, max_length: usize) -> Self {
+        let list = match storage {
+            #[cfg(feature = "sqlite")]
+            Storage::Sqlite => Database::with(|db| db.load_history()),
+            _ => serde_json::from_str(&read_config(HistoryFile)).map_err(ParseDB),
+        };
+
+        let mut history = Self {
+            list: list.unwrap_or_default(),
+            storage,
+            max_length,
+            state: ListState::default(),
+        };
+        history.trim();
+        history
+    }
+
+    fn save(&self) -> Result<()> {
+        match self.storage {
+            #[cfg(feature = "sqlite")]
+            Storage::Sqlite => Database::with(|db| db.save_history(&self.list)),
+            _ => {
+                let string = serde_json::to_string(&self.list).unwrap();
+                write_config(HistoryFile, &string)
+            }
         }
     }
 
-    fn save(&self) {
-        let string = serde_json::to_string(&self.list).unwrap();
-        write_config(HistoryFile, &string);
+    fn trim(&mut self) {
+        let excess = self.list.len().saturating_sub(self.max_length);
+        self.list.drain(..excess);
     }
 
-    pub(crate) fn add(&mut self, video: Video) {
-        let mimimal_video = MinimalVideo::from(video);
+    /// Put `video` of channel `channel_id` on top of the history
+    pub(crate) fn add(&mut self, video: &Video, channel_id: &str) -> Result<()> {
+        let mimimal_video = MinimalVideo::new(video, channel_id);
 
         // remove if already exist and put new one in
-        for i in 0..self.list.len() {
-            if self.list[i] == mimimal_video {
-                self.list.remove(i);
-                break;
-            }
+        self.list.retain(|entry| !entry.is_same_video(&mimimal_video));
+        self.list.push(mimimal_video);
+        self.trim();
+
+        self.save()
+    }
+
+    /// The player of the latest entry of `link` was closed after `seconds`
+    pub(crate) fn set_watched(&mut self, link: &str, seconds: u64) -> Result<()> {
+        match self.list.iter_mut().rev().find(|entry| entry.link == link) {
+            Some(entry) => entry.watched = Some(seconds),
+            None => return Ok(()),
         }
 
-        self.list.push(mimimal_video);
+        self.save()
+    }
+
+    /// Put the selected entry on top again, as a newly opened video
+    pub(crate) fn reopen_selected(&mut self) -> Result<()> {
+        let Some(i) = self.selected_index() else {
+            return Ok(());
+        };
+        let mut entry = self.list.remove(i);
+        entry.opened = Local::now().timestamp();
+        entry.watched = None;
+        self.list.push(entry);
 
         self.save()
     }
 
+    pub(crate) fn next(&mut self) {
+        let i = self.state.selected().map_or(0, |i| i + 1);
+        self.select(Some(i));
+    }
+
+    pub(crate) fn prev(&mut self) {
+        let i = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.select(Some(i));
+    }
+
+    pub(crate) fn select(&mut self, i: Option<usize>) {
+        let i = i.filter(|_| !self.list.is_empty()).map(|i| i.min(self.list.len() - 1));
+        self.state.select(i);
+    }
+
+    /// Position of the selected entry in `list`, which is shown in reverse
+    fn selected_index(&self) -> Option<usize> {
+        let i = self.state.selected()?;
+        self.list.len().checked_sub(i + 1)
+    }
+
+    pub(crate) fn selected(&self) -> Option<&MinimalVideo> {
+        self.list.get(self.selected_index()?)
+    }
+
+    pub(crate) fn remove_selected(&mut self) -> Result<()> {
+        let Some(i) = self.selected_index() else {
+            return Ok(());
+        };
+        self.list.remove(i);
+        self.select(self.state.selected());
+
+        self.save()
+    }
+
+    pub(crate) fn clear(&mut self) -> Result<()> {
+        self.list.clear();
+        self.select(None);
+
+        self.save()
+    }
+
+    /// Oldest entry first
+    pub(crate) fn entries(&self) -> &[MinimalVideo] {
+        &self.list
+    }
+
+    pub(crate) fn state_mut(&mut self) -> &mut ListState {
+        &mut self.state
+    }
+
     pub(crate) fn to_list_items(&self) -> Vec<ListItem> {
         self.list.iter().map(|v| v.to_list_item()).rev().collect()
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-struct MinimalVideo {
+pub(crate) struct MinimalVideo {
     title: String,
     channel: String,
+    /// entries of older versions only know title and channel
+    #[serde(default)]
+    link: String,
+    #[serde(default)]
+    channel_id: String,
+    /// when the video was opened (unix time)
+    #[serde(default)]
+    opened: i64,
+    /// seconds until the player was closed
+    #[serde(default)]
+    watched: Option<u64>,
+}
+
+impl MinimalVideo {
+    fn new(video: &Video, channel_id: &str) -> Self {
+        MinimalVideo {
+            title: video.title().clone(),
+            channel: video.origin_channel_name().clone(),
+            link: video.link().clone(),
+            channel_id: channel_id.to_string(),
+            opened: Local::now().timestamp(),
+            watched: None,
+        }
+    }
+
+    fn is_same_video(&self, other: &Self) -> bool {
+        if self.link.is_empty() || other.link.is_empty() {
+            self.title == other.title && self.channel == other.channel
+        } else {
+            self.link == other.link
+        }
+    }
+
+    pub(crate) fn title(&self) -> &String {
+        &self.title
+    }
+
+    pub(crate) fn link(&self) -> &String {
+        &self.link
+    }
+
+    pub(crate) fn channel(&self) -> &String {
+        &self.channel
+    }
+
+    pub(crate) fn channel_id(&self) -> &String {
+        &self.channel_id
+    }
+
+    pub(crate) fn opened(&self) -> i64 {
+        self.opened
+    }
 }
 
 impl ToTuiListItem for MinimalVideo {
@@ -61,24 +215,29 @@ impl ToTuiListItem for MinimalVideo {
         let channel = format!("{} {} - ", tui::symbols::DOT, &self.channel);
         let title = self.title.clone();
 
+        let mut facts = Vec::new();
+        if let Some(opened) = Local.timestamp_opt(self.opened, 0).single().filter(|_| self.opened > 0) {
+            facts.push(opened.format("%d.%m. %H:%M").to_string());
+        }
+        if let Some(watched) = self.watched {
+            facts.push(format!("watched {}", format_seconds(watched)));
+        }
+        let facts = if facts.is_empty() {
+            String::new()
+        } else {
+            format!("  ({})", facts.join(", "))
+        };
+
         let style = Style::default().fg(Color::DarkGray);
 
         ListItem::new(Line::from(vec![
             Span::styled(channel, style),
             Span::styled(title, style.add_modifier(Modifier::ITALIC)),
+            Span::styled(facts, style),
         ]))
     }
 }
 
-impl From<Video> for MinimalVideo {
-    fn from(video: Video) -> MinimalVideo {
-        MinimalVideo {
-            title: video.title().clone(),
-            channel: video.origin_channel_name().clone(),
-        }
-    }
-}
-
 /*
  * #[cfg(test)]
  * pub mod tests {
@@ -116,3 +275,36 @@ impl From<Video> for MinimalVideo {
  *         let _ = remove_file(file);
  *     }
  * } */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_entries_and_selection() {
+        // entries of older versions only have title and channel
+        let list: Vec<MinimalVideo> = serde_json::from_str(
+            r#"[{"title": "First", "channel": "A"}, {"title": "Second", "channel": "B", "link": "https://example.com/2"}]"#,
+        )
+        .unwrap();
+        assert_eq!(list[0].link, "");
+        assert_eq!(list[0].watched, None);
+
+        let mut history = History {
+            list,
+            max_length: 1,
+            ..History::default()
+        };
+
+        // the newest entry is shown first
+        history.select(Some(0));
+        assert_eq!(history.selected().unwrap().title(), "Second");
+        history.next();
+        history.next();
+        assert_eq!(history.selected().unwrap().title(), "First");
+
+        history.trim();
+        assert_eq!(history.list.len(), 1);
+        assert_eq!(history.list[0].title(), "Second");
+    }
+}
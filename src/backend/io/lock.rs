@@ -0,0 +1,69 @@
+use crate::backend::{
+    io::lock_path,
+    Error::{AlreadyRunning, WriteFile},
+    Result,
+};
+use fs2::{lock_contended_error, FileExt};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+/// Advisory lock on `tyt.lock` in the state dir, held by the interactive instance.
+///
+/// Two instances would both rewrite the DB and the last one to save would revert the
+/// marks of the other. The lock is released when this is dropped or the process dies.
+pub(crate) struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    pub(crate) fn acquire() -> Result<Self> {
+        Self::acquire_at(&lock_path())
+    }
+
+    fn acquire_at(path: &Path) -> Result<Self> {
+        let write_error = |error| WriteFile(path.to_path_buf(), error);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .map_err(write_error)?;
+
+        if let Err(error) = file.try_lock_exclusive() {
+            return if error.raw_os_error() == lock_contended_error().raw_os_error() {
+                Err(AlreadyRunning(path.to_path_buf()))
+            } else {
+                Err(write_error(error))
+            };
+        }
+
+        // only informational, for whoever looks at the file
+        let _ = file.set_len(0).and_then(|_| writeln!(file, "{}", std::process::id()));
+
+        Ok(Self { _file: file })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_lock_is_refused() {
+        let dir = std::env::temp_dir().join(format!("tyt_lock_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tyt.lock");
+
+        let lock = InstanceLock::acquire_at(&path).unwrap();
+        assert!(matches!(InstanceLock::acquire_at(&path), Err(AlreadyRunning(_))));
+
+        drop(lock);
+        assert!(InstanceLock::acquire_at(&path).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,22 +1,45 @@
 use crate::{
     backend::{
         data::channel_list::ChannelList,
-        io::{config::Config, history::History, subscriptions::Subscriptions},
+        io::{
+            archive::Archive, config::Config, feed_cache::FeedCache, history::History, open_counts::OpenCounts,
+            subscriptions::Subscriptions,
+        },
+        Error::WriteFile,
+        Result,
     },
     notification::notify_error,
 };
 use dirs_next::home_dir;
+use log::*;
 use std::{
-    fs::{create_dir_all, OpenOptions},
+    env,
+    fs::{self, create_dir_all, File, OpenOptions},
     io::{ErrorKind, Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
 };
 
+pub(crate) mod archive;
 pub(crate) mod config;
+pub(crate) mod feed_cache;
 pub(crate) mod history;
+pub(crate) mod lock;
+pub(crate) mod open_counts;
+#[cfg(feature = "sqlite")]
+pub(crate) mod sqlite;
 pub(crate) mod subscriptions;
 
-const CONFIG_PATH: &str = ".config/tyt/";
+/// where all files were kept before the XDG directories were used
+const LEGACY_PATH: &str = ".config/tyt/";
+const APP_DIR: &str = "tyt";
+
+const LOG_FILE: &str = "debug.log";
+/// every profile has its own dir in here, next to the files of the default profile
+const PROFILES_DIR: &str = "profiles";
 
 const CONFIG_FILE: &str = "config.yml";
 
@@ -25,14 +48,34 @@ const DB_FILE: &str = "db.json";
 #[cfg(not(debug_assertions))]
 const HISTORY_FILE: &str = "history.json";
 #[cfg(not(debug_assertions))]
+const ARCHIVE_FILE: &str = "archive.json";
+#[cfg(not(debug_assertions))]
+const OPEN_COUNTS_FILE: &str = "open_counts.json";
+#[cfg(not(debug_assertions))]
+const FEED_CACHE_FILE: &str = "feed_cache.json";
+#[cfg(not(debug_assertions))]
 const SUBSCRIPTIONS_FILE: &str = "subscriptions.yml";
+#[cfg(not(debug_assertions))]
+const LOCK_FILE: &str = "tyt.lock";
+#[cfg(all(feature = "sqlite", not(debug_assertions)))]
+const SQLITE_FILE: &str = "db.sqlite";
 
 #[cfg(debug_assertions)]
 const DB_FILE: &str = "db_debug.json";
 #[cfg(debug_assertions)]
 const HISTORY_FILE: &str = "history_debug.json";
 #[cfg(debug_assertions)]
+const ARCHIVE_FILE: &str = "archive_debug.json";
+#[cfg(debug_assertions)]
+const OPEN_COUNTS_FILE: &str = "open_counts_debug.json";
+#[cfg(debug_assertions)]
+const FEED_CACHE_FILE: &str = "feed_cache_debug.json";
+#[cfg(debug_assertions)]
 const SUBSCRIPTIONS_FILE: &str = "subscriptions_debug.yml";
+#[cfg(debug_assertions)]
+const LOCK_FILE: &str = "tyt_debug.lock";
+#[cfg(all(feature = "sqlite", debug_assertions))]
+const SQLITE_FILE: &str = "db_debug.sqlite";
 
 #[allow(clippy::enum_variant_names)]
 #[derive(PartialEq)]
@@ -40,15 +83,40 @@ pub(crate) enum FileType {
     ConfigFile,
     DbFile,
     HistoryFile,
+    ArchiveFile,
+    OpenCountsFile,
+    FeedCacheFile,
     SubscriptionsFile,
 }
 
 impl FileType {
+    fn path(&self) -> PathBuf {
+        match self {
+            // a profile can have its own config, otherwise the shared one is used
+            FileType::ConfigFile => {
+                let own = get_profile_dir(DirType::Config).join(self.file());
+                if current_profile().is_some() && own.exists() {
+                    own
+                } else {
+                    get_dir(DirType::Config).join(self.file())
+                }
+            }
+            FileType::SubscriptionsFile => get_profile_dir(DirType::Config).join(self.file()),
+            FileType::DbFile | FileType::HistoryFile | FileType::ArchiveFile | FileType::OpenCountsFile => {
+                get_profile_dir(DirType::Data).join(self.file())
+            }
+            FileType::FeedCacheFile => get_profile_dir(DirType::Cache).join(self.file()),
+        }
+    }
+
     fn file(&self) -> &str {
         match self {
             FileType::ConfigFile => CONFIG_FILE,
             FileType::DbFile => DB_FILE,
             FileType::HistoryFile => HISTORY_FILE,
+            FileType::ArchiveFile => ARCHIVE_FILE,
+            FileType::OpenCountsFile => OPEN_COUNTS_FILE,
+            FileType::FeedCacheFile => FEED_CACHE_FILE,
             FileType::SubscriptionsFile => SUBSCRIPTIONS_FILE,
         }
     }
@@ -59,21 +127,24 @@ impl FileType {
             FileType::ConfigFile => serde_yaml::to_string(&Config::default()).unwrap(),
             FileType::DbFile => serde_json::to_string(&ChannelList::default()).unwrap(),
             FileType::HistoryFile => serde_json::to_string(&History::default()).unwrap(),
+            FileType::ArchiveFile => serde_json::to_string(&Archive::default()).unwrap(),
+            FileType::OpenCountsFile => serde_json::to_string(&OpenCounts::default()).unwrap(),
+            FileType::FeedCacheFile => serde_json::to_string(&FeedCache::default()).unwrap(),
             FileType::SubscriptionsFile => {
                 serde_yaml::to_string(&Subscriptions::default()).unwrap()
             }
         };
 
-        write_config(self, &string);
+        if let Err(error) = write_config(self, &string) {
+            notify_error(&error.to_string());
+        }
         string
     }
 }
 
-/// Read in config dir
+/// Read the file of `file_type`, a default is written if it does not exist
 pub(crate) fn read_config(file_type: FileType) -> String {
-    let config_dir = get_config_dir();
-
-    let file_path = config_dir.join(file_type.file());
+    let file_path = file_type.path();
 
     let file_result = OpenOptions::new().read(true).open(file_path);
 
@@ -97,44 +168,395 @@ pub(crate) fn read_config(file_type: FileType) -> String {
     }
 }
 
-/// Write in config dir
-pub(crate) fn write_config(r#type: FileType, content: &str) {
-    let config_dir = get_config_dir();
+/// Read the file of `file_type`, without writing a default if it does not exist
+#[cfg(feature = "sqlite")]
+pub(crate) fn read_existing(file_type: FileType) -> std::io::Result<Option<String>> {
+    match fs::read_to_string(file_type.path()) {
+        Ok(content) => Ok(Some(content)),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub(crate) fn sqlite_path() -> PathBuf {
+    get_profile_dir(DirType::Data).join(SQLITE_FILE)
+}
+
+pub(crate) fn log_path() -> PathBuf {
+    get_profile_dir(DirType::State).join(LOG_FILE)
+}
+
+/// one lock per profile, so different profiles can run at the same time
+fn lock_path() -> PathBuf {
+    get_profile_dir(DirType::State).join(LOCK_FILE)
+}
+
+/// Write the file of `file_type`.
+///
+/// The content is written to a temporary file that replaces the target only once it is
+/// completely on disk, so a crash never leaves a truncated file behind. The previous
+/// version is kept as `<file>.bak`.
+pub(crate) fn write_config(r#type: FileType, content: &str) -> Result<()> {
+    let file_path = r#type.path();
+
+    write_atomic(&file_path, content.as_bytes()).map_err(|error| WriteFile(file_path, error))
+}
+
+/// Write a file outside of the tyt directories, as atomically as the files inside
+pub(crate) fn write_file(path: &Path, content: &str) -> Result<()> {
+    write_atomic(path, content.as_bytes()).map_err(|error| WriteFile(path.to_path_buf(), error))
+}
+
+/// Number of the next temporary file, so concurrent writers of a file never share one
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
-    let file_path = config_dir.join(r#type.file());
+fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let tmp_path = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let bak_path = dir.join(format!("{}.bak", file_name));
 
-    let mut file = OpenOptions::new()
+    let mut tmp = OpenOptions::new()
         .write(true)
         .truncate(true)
         .create(true)
-        .open(file_path)
-        .unwrap();
+        .open(&tmp_path)?;
+
+    if let Err(error) = tmp.write_all(content).and_then(|_| tmp.sync_all()) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(error);
+    }
+
+    // keep the last version; hard links are cheap, copy if the fs does not support them
+    if path.exists() {
+        let _ = fs::remove_file(&bak_path);
+        if fs::hard_link(path, &bak_path).is_err() {
+            fs::copy(path, &bak_path)?;
+        }
+    }
+
+    fs::rename(&tmp_path, path)?;
+
+    // persist the rename itself
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum DirType {
+    /// config and subscriptions
+    Config,
+    /// DB and history
+    Data,
+    /// log and lock file
+    State,
+    /// feed cache
+    Cache,
+}
+
+/// Directories tyt keeps its files in
+#[derive(Debug, PartialEq)]
+struct Dirs {
+    config: PathBuf,
+    data: PathBuf,
+    state: PathBuf,
+    cache: PathBuf,
+}
+
+static DIRS: OnceLock<Dirs> = OnceLock::new();
+/// `None` is the default profile
+static PROFILE: OnceLock<Option<String>> = OnceLock::new();
 
-    // if r#type == FileType::DbFile {
-    //     eprintln!("######");
-    //     eprintln!("content: {:?}", content);
-    //     eprintln!("######");
-    // }
-    let _ = file.write_all(content.as_bytes());
+impl Dirs {
+    /// Everything in one directory, e.g. for a separate test profile
+    fn single(home: PathBuf) -> Self {
+        Self {
+            config: home.clone(),
+            data: home.clone(),
+            state: home.clone(),
+            cache: home,
+        }
+    }
+
+    /// XDG base directories; `var` looks up environment variables
+    fn xdg(home: &Path, var: impl Fn(&str) -> Option<String>) -> Self {
+        // relative paths are invalid according to the spec and ignored
+        let base = |name: &str, default: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+                .unwrap_or_else(|| home.join(default))
+                .join(APP_DIR)
+        };
+
+        Self {
+            config: base("XDG_CONFIG_HOME", ".config"),
+            data: base("XDG_DATA_HOME", ".local/share"),
+            state: base("XDG_STATE_HOME", ".local/state"),
+            cache: base("XDG_CACHE_HOME", ".cache"),
+        }
+    }
+
+    fn from_env() -> Self {
+        Self::xdg(&get_home_dir(), |name| env::var(name).ok())
+    }
+
+    fn get(&self, dir: DirType) -> &Path {
+        match dir {
+            DirType::Config => &self.config,
+            DirType::Data => &self.data,
+            DirType::State => &self.state,
+            DirType::Cache => &self.cache,
+        }
+    }
 }
 
-// private function to create and read config dir
-fn get_config_dir() -> PathBuf {
-    // crate config dir if not exists
-    let home_dir = match home_dir() {
+/// Set the directories once at start. With `home` (`--config-dir` or `TYT_HOME`) all
+/// files are kept there, otherwise the XDG base directories are used.
+/// Subscriptions, DB and history of `profile` are kept in a dir of their own.
+pub(crate) fn init_dirs(home: Option<PathBuf>, profile: Option<String>) {
+    let _ = PROFILE.set(profile);
+
+    let dirs = match home {
+        Some(home) => Dirs::single(home),
+        None => Dirs::from_env(),
+    };
+
+    if DIRS.set(dirs).is_err() {
+        warn!("Directories were already set");
+    }
+}
+
+/// Move files that older versions kept in `~/.config/tyt` to the XDG directories.
+/// Files that already exist in the new place are left alone.
+pub(crate) fn migrate_legacy_files() {
+    let legacy = get_home_dir().join(LEGACY_PATH);
+    migrate_files(&legacy, DIRS.get_or_init(Dirs::from_env));
+}
+
+fn migrate_files(legacy: &Path, dirs: &Dirs) {
+    if [&dirs.config, &dirs.data, &dirs.cache].iter().all(|dir| *dir == legacy) {
+        return;
+    }
+
+    let files = [
+        (CONFIG_FILE, DirType::Config),
+        (SUBSCRIPTIONS_FILE, DirType::Config),
+        (DB_FILE, DirType::Data),
+        (HISTORY_FILE, DirType::Data),
+        (FEED_CACHE_FILE, DirType::Cache),
+        #[cfg(feature = "sqlite")]
+        (SQLITE_FILE, DirType::Data),
+    ];
+
+    for (file, dir) in files {
+        // backups and sqlite journals move along with their file
+        for suffix in ["", ".bak", "-wal", "-shm"] {
+            let name = format!("{}{}", file, suffix);
+            let from = legacy.join(&name);
+            let to = dirs.get(dir).join(&name);
+
+            if from == to || !from.exists() || to.exists() {
+                continue;
+            }
+
+            let moved = create_dir_all(dirs.get(dir)).and_then(|_| move_file(&from, &to));
+            match moved {
+                Ok(()) => info!("Moved {} to {}", from.display(), to.display()),
+                Err(error) => {
+                    notify_error(&format!("Could not move {} to {}: {}", from.display(), to.display(), error));
+                }
+            }
+        }
+    }
+}
+
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    // rename does not work across file systems
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn current_profile() -> Option<&'static str> {
+    PROFILE.get().and_then(Option::as_deref)
+}
+
+/// Names of all profiles that have files, without the default profile
+pub(crate) fn list_profiles() -> Vec<String> {
+    let mut profiles: Vec<String> = [DirType::Config, DirType::Data]
+        .into_iter()
+        .filter_map(|dir| fs::read_dir(get_dir(dir).join(PROFILES_DIR)).ok())
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_valid_profile_name(name))
+        .collect();
+
+    profiles.sort();
+    profiles.dedup();
+    profiles
+}
+
+/// Profile names are used as directory names
+pub(crate) fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !name.starts_with('.')
+}
+
+fn get_home_dir() -> PathBuf {
+    match home_dir() {
         Some(p) => p,
         None => {
             let error = "could not read home dir";
             notify_error(error);
             panic!("{}", error);
         }
+    }
+}
+
+// private function to create and read a dir
+fn get_dir(dir: DirType) -> PathBuf {
+    let path = DIRS.get_or_init(Dirs::from_env).get(dir).to_path_buf();
+
+    // crate dir if not exists
+    if let Err(error) = create_dir_all(&path) {
+        if error.kind() == ErrorKind::PermissionDenied {
+            notify_error(&format!("Permission to {} denied", path.display()));
+        }
+    }
+
+    path
+}
+
+// dir of the current profile in `dir`
+fn get_profile_dir(dir: DirType) -> PathBuf {
+    let path = match current_profile() {
+        Some(profile) => get_dir(dir).join(PROFILES_DIR).join(profile),
+        None => return get_dir(dir),
     };
-    let path = home_dir.join(CONFIG_PATH);
+
     if let Err(error) = create_dir_all(&path) {
         if error.kind() == ErrorKind::PermissionDenied {
-            notify_error("Permission to config dir denied");
+            notify_error(&format!("Permission to {} denied", path.display()));
         }
     }
 
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_atomic_keeps_backup() {
+        let dir = std::env::temp_dir().join(format!("tyt_write_test_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("db.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_to_string(dir.join("db.json.bak")).unwrap(), "first");
+        let names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(names.iter().all(|name| !name.ends_with(".tmp")), "{:?}", names);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_writers_do_not_share_a_temporary_file() {
+        let dir = std::env::temp_dir().join(format!("tyt_concurrent_write_test_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("feed_cache.json");
+
+        let contents: Vec<String> = (0..8).map(|i| format!("{{\"writer\": {}}}", i).repeat(1000)).collect();
+        std::thread::scope(|scope| {
+            for content in &contents {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        write_atomic(path, content.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+
+        // one complete version wins
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains(&written));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn xdg_dirs() {
+        let home = Path::new("/home/user");
+        let var = |name: &str| match name {
+            "XDG_DATA_HOME" => Some("/data".to_string()),
+            "XDG_STATE_HOME" => Some("relative/state".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            Dirs::xdg(home, var),
+            Dirs {
+                config: PathBuf::from("/home/user/.config/tyt"),
+                data: PathBuf::from("/data/tyt"),
+                state: PathBuf::from("/home/user/.local/state/tyt"),
+                cache: PathBuf::from("/home/user/.cache/tyt"),
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_to_other_config_home() {
+        let home = std::env::temp_dir().join(format!("tyt_migrate_test_{}", std::process::id()));
+        let legacy = home.join(LEGACY_PATH);
+        create_dir_all(&legacy).unwrap();
+        for file in [CONFIG_FILE, SUBSCRIPTIONS_FILE, DB_FILE] {
+            fs::write(legacy.join(file), file).unwrap();
+        }
+
+        // only the config dir differs from the legacy dir
+        let config_home = home.join("config").to_string_lossy().into_owned();
+        let dirs = Dirs::xdg(&home, |name| match name {
+            "XDG_CONFIG_HOME" => Some(config_home.clone()),
+            "XDG_DATA_HOME" | "XDG_CACHE_HOME" => Some(home.join(".config").to_string_lossy().into_owned()),
+            _ => None,
+        });
+        fs::create_dir_all(&dirs.config).unwrap();
+        fs::write(dirs.config.join(SUBSCRIPTIONS_FILE), "newer").unwrap();
+
+        migrate_files(&legacy, &dirs);
+
+        assert_eq!(fs::read_to_string(dirs.config.join(CONFIG_FILE)).unwrap(), CONFIG_FILE);
+        assert!(!legacy.join(CONFIG_FILE).exists());
+        // files in the new place are kept
+        assert_eq!(fs::read_to_string(dirs.config.join(SUBSCRIPTIONS_FILE)).unwrap(), "newer");
+        assert!(legacy.join(SUBSCRIPTIONS_FILE).exists());
+        assert!(legacy.join(DB_FILE).exists());
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+use crate::backend::{
+    io::{history::MinimalVideo, read_config, write_config, FileType::OpenCountsFile},
+    Result,
+};
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How often videos were opened, for the statistics. Unlike the history this counts
+/// every open and is never trimmed.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct OpenCounts {
+    /// opened videos per local day, `YYYY-MM-DD`
+    #[serde(default)]
+    per_day: BTreeMap<String, usize>,
+    /// opened videos per channel id
+    #[serde(default)]
+    per_channel: BTreeMap<String, usize>,
+}
+
+impl OpenCounts {
+    /// Load the counts; without any they are taken from `history`, as far as it goes
+    pub(crate) fn load(history: &[MinimalVideo]) -> Self {
+        let counts: Self = serde_json::from_str(&read_config(OpenCountsFile)).unwrap_or_default();
+        if counts.per_day.is_empty() && counts.per_channel.is_empty() {
+            return Self::from_history(history);
+        }
+        counts
+    }
+
+    fn from_history(history: &[MinimalVideo]) -> Self {
+        let mut counts = Self::default();
+        for entry in history {
+            // entries of older versions only know the channel name
+            if !entry.channel_id().is_empty() {
+                *counts.per_channel.entry(entry.channel_id().to_string()).or_default() += 1;
+            }
+            if let Some(opened) = Local.timestamp_opt(entry.opened(), 0).single().filter(|_| entry.opened() > 0) {
+                counts.add_day(opened);
+            }
+        }
+        counts
+    }
+
+    /// Count a video of channel `channel_id` opened now
+    pub(crate) fn add(&mut self, channel_id: &str) -> Result<()> {
+        *self.per_channel.entry(channel_id.to_string()).or_default() += 1;
+        self.add_day(Local::now());
+
+        let string = serde_json::to_string(self).unwrap();
+        write_config(OpenCountsFile, &string)
+    }
+
+    fn add_day(&mut self, opened: DateTime<Local>) {
+        *self.per_day.entry(opened.format("%Y-%m-%d").to_string()).or_default() += 1;
+    }
+
+    pub(crate) fn per_day(&self) -> &BTreeMap<String, usize> {
+        &self.per_day
+    }
+
+    pub(crate) fn per_channel(&self) -> &BTreeMap<String, usize> {
+        &self.per_channel
+    }
+}
@@ -0,0 +1,406 @@
+use crate::backend::{
+    data::{channel::Channel, channel_list::ChannelList, migration::DB_VERSION, video::Video},
+    io::{read_existing, sqlite_path, FileType::{DbFile, HistoryFile}},
+    Error::{self, Database as DatabaseError, NewerDbVersion, ParseDB},
+    Result,
+};
+use log::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    path::Path,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS channels (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        position INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS videos (
+        channel_id TEXT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+        link TEXT NOT NULL,
+        video_id TEXT,
+        marked INTEGER NOT NULL,
+        fav INTEGER NOT NULL,
+        hidden INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (channel_id, link)
+    );
+    CREATE INDEX IF NOT EXISTS videos_link ON videos(link);
+    CREATE INDEX IF NOT EXISTS videos_video_id ON videos(video_id);
+    CREATE TABLE IF NOT EXISTS history (
+        position INTEGER PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+";
+
+/// set once `db.json` and `history.json` were copied into the database
+const JSON_MIGRATED: &str = "json_migrated";
+/// newest `DB_VERSION` of the builds that opened the database
+const DB_VERSION_KEY: &str = "db_version";
+
+/// How long to wait for the fetch threads or another instance to release the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The connection of this session, opened on first use. The profile cannot change while
+/// tyt runs, so it always points to the same file.
+static SESSION: Mutex<Option<Database>> = Mutex::new(None);
+
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        DatabaseError(error.to_string())
+    }
+}
+
+/// Channels, videos and the history in `db.sqlite`.
+///
+/// Videos are stored as json next to the columns that are looked up or changed often,
+/// so new fields of `Video` need no schema change.
+pub(crate) struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    /// Run `f` with the database of this session, opening it if this is the first use
+    pub(crate) fn with<T>(f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let mut session = SESSION.lock().unwrap_or_else(PoisonError::into_inner);
+        let db = match session.as_mut() {
+            Some(db) => db,
+            None => session.insert(Self::open()?),
+        };
+        f(db)
+    }
+
+    /// Open the database in the data dir. The first time, `db.json` and `history.json`
+    /// are migrated into it.
+    fn open() -> Result<Self> {
+        let mut db = Self::open_at(&sqlite_path())?;
+
+        if db.meta(JSON_MIGRATED)?.is_none() {
+            let read_error = |error: std::io::Error| DatabaseError(format!("Could not read JSON data: {}", error));
+            let channels = read_existing(DbFile).map_err(read_error)?;
+            let history = read_existing(HistoryFile).map_err(read_error)?;
+            db.migrate_from_json(channels.as_deref(), history.as_deref())?;
+        }
+
+        Ok(db)
+    }
+
+    fn open_at(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.execute_batch(SCHEMA)?;
+
+        let db = Self { conn };
+        let stored = db.meta(DB_VERSION_KEY)?.and_then(|version| version.parse::<u64>().ok());
+        match stored {
+            Some(version) if version > DB_VERSION => return Err(NewerDbVersion(version, DB_VERSION)),
+            Some(DB_VERSION) => {}
+            // the schema is up to date now, so a missing or older stamp is raised
+            _ => {
+                db.conn.execute(
+                    "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+                    params![DB_VERSION_KEY, DB_VERSION.to_string()],
+                )?;
+            }
+        }
+
+        Ok(db)
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get(0))
+            .optional()?;
+        Ok(value)
+    }
+
+    /// Copy the json files into the database; an unparsable DB is an error so it is not lost
+    fn migrate_from_json(&mut self, channels: Option<&str>, history: Option<&str>) -> Result<()> {
+        let channels = match channels {
+            Some(json) => ChannelList::from_json(json)?,
+            None => ChannelList::default(),
+        };
+
+        let history = history
+            .map(|json| {
+                serde_json::from_str::<Vec<serde_json::Value>>(json).unwrap_or_else(|error| {
+                    warn!("Could not migrate history: {}", error);
+                    Vec::new()
+                })
+            })
+            .unwrap_or_default();
+
+        let tx = self.conn.transaction()?;
+        save_channels(&tx, channels.channels())?;
+        save_history(&tx, &history)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            params![JSON_MIGRATED, chrono::Local::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+
+        info!("Migrated JSON data into the database");
+        Ok(())
+    }
+
+    pub(crate) fn load_channels(&self) -> Result<Vec<Channel>> {
+        let mut channel_stmt = self
+            .conn
+            .prepare("SELECT id, name FROM channels ORDER BY position")?;
+        let mut video_stmt = self
+            .conn
+            .prepare("SELECT data, hidden FROM videos WHERE channel_id = ?1 ORDER BY rowid")?;
+
+        let ids = channel_stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut channels = Vec::with_capacity(ids.len());
+        for (id, name) in ids {
+            let mut videos = Vec::new();
+            let mut hidden_videos = Vec::new();
+
+            let rows = video_stmt.query_map([&id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+            })?;
+            for row in rows {
+                let (data, hidden) = row?;
+                let video: Video = serde_json::from_str(&data).map_err(ParseDB)?;
+                if hidden {
+                    hidden_videos.push(video);
+                } else {
+                    videos.push(video);
+                }
+            }
+
+            channels.push(Channel::from_stored(id, name, videos, hidden_videos));
+        }
+
+        Ok(channels)
+    }
+
+    /// Replace all stored channels
+    pub(crate) fn save_channels(&mut self, channels: &[Channel]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        save_channels(&tx, channels)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store one channel at `position`; all other channels are left alone
+    pub(crate) fn save_channel(&mut self, position: usize, channel: &Channel) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        save_channel(&tx, position, channel)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Update a single stored video, e.g. after it was marked.
+    /// Returns `false` if the video is not stored yet.
+    pub(crate) fn update_video(&self, channel_id: &str, video: &Video) -> Result<bool> {
+        let data = serde_json::to_string(video).map_err(ParseDB)?;
+        let changed = self.conn.execute(
+            "UPDATE videos SET marked = ?1, fav = ?2, data = ?3 WHERE channel_id = ?4 AND link = ?5",
+            params![video.marked(), video.is_fav(), data, channel_id, video.link()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub(crate) fn load_history<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        let mut stmt = self.conn.prepare("SELECT data FROM history ORDER BY position")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut history = Vec::new();
+        for row in rows {
+            match serde_json::from_str(&row?) {
+                Ok(entry) => history.push(entry),
+                Err(error) => warn!("Skipping history entry: {}", error),
+            }
+        }
+
+        Ok(history)
+    }
+
+    pub(crate) fn save_history<T: Serialize>(&mut self, history: &[T]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        save_history(&tx, history)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn save_channels(conn: &Connection, channels: &[Channel]) -> Result<()> {
+    let stored = conn
+        .prepare("SELECT id FROM channels")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for id in stored {
+        if !channels.iter().any(|channel| channel.id() == &id) {
+            conn.execute("DELETE FROM channels WHERE id = ?1", [&id])?;
+        }
+    }
+
+    for (position, channel) in channels.iter().enumerate() {
+        save_channel(conn, position, channel)?;
+    }
+
+    Ok(())
+}
+
+fn save_channel(conn: &Connection, position: usize, channel: &Channel) -> Result<()> {
+    conn.execute(
+        "INSERT INTO channels (id, name, position) VALUES (?1, ?2, ?3)
+            ON CONFLICT(id) DO UPDATE SET name = excluded.name, position = excluded.position",
+        params![channel.id(), channel.name(), position as i64],
+    )?;
+    conn.execute("DELETE FROM videos WHERE channel_id = ?1", [channel.id()])?;
+
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR REPLACE INTO videos (channel_id, link, video_id, marked, fav, hidden, data)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+
+    let videos = channel.videos.iter().map(|video| (video, false));
+    let hidden = channel.hidden_videos.iter().map(|video| (video, true));
+    for (video, hidden) in videos.chain(hidden) {
+        let data = serde_json::to_string(video).map_err(ParseDB)?;
+        stmt.execute(params![
+            channel.id(),
+            video.link(),
+            video.id(),
+            video.marked(),
+            video.is_fav(),
+            hidden,
+            data
+        ])?;
+    }
+
+    Ok(())
+}
+
+fn save_history<T: Serialize>(conn: &Connection, history: &[T]) -> Result<()> {
+    conn.execute("DELETE FROM history", [])?;
+
+    let mut stmt = conn.prepare_cached("INSERT INTO history (position, data) VALUES (?1, ?2)")?;
+    for (position, entry) in history.iter().enumerate() {
+        let data = serde_json::to_string(entry).map_err(ParseDB)?;
+        stmt.execute(params![position as i64, data])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(title: &str) -> Video {
+        serde_json::from_value(serde_json::json!({
+            "title": title,
+            "link": format!("https://example.com/{}", title),
+            "origin_url": "https://example.com/feed.xml",
+            "origin_channel_name": "Channel",
+            "marked": false,
+            "pubDate": "2024-01-01T00:00:00+00:00",
+        }))
+        .unwrap()
+    }
+
+    fn open_temp(name: &str) -> (Database, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("tyt_sqlite_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        (Database::open_at(&dir.join("db.sqlite")).unwrap(), dir)
+    }
+
+    #[test]
+    fn channels_round_trip() {
+        let (mut db, dir) = open_temp("channels");
+
+        let channel = Channel::from_stored(
+            "UC123".to_string(),
+            "Channel".to_string(),
+            vec![video("Podcast"), video("Vlog")],
+            vec![video("Hidden")],
+        );
+        db.save_channels(std::slice::from_ref(&channel)).unwrap();
+
+        let loaded = db.load_channels().unwrap();
+        assert_eq!(loaded, vec![channel.clone()]);
+        assert_eq!(loaded[0].hidden_videos.len(), 1);
+
+        // marks only touch their row
+        let mut marked = channel.videos[0].clone();
+        marked.mark(true);
+        assert!(db.update_video("UC123", &marked).unwrap());
+        assert!(!db.update_video("UC123", &video("Unknown")).unwrap());
+        assert!(db.load_channels().unwrap()[0].videos[0].marked());
+
+        // removed channels are deleted with their videos
+        db.save_channels(&[]).unwrap();
+        assert!(db.load_channels().unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn migrates_json_once() {
+        let (mut db, dir) = open_temp("migration");
+
+        let db_json = r#"{"channels":[{"name":"Channel","id":"UC123","videos":[]}]}"#;
+        let history_json = r#"[{"title":"Podcast","channel":"Channel"}]"#;
+        db.migrate_from_json(Some(db_json), Some(history_json)).unwrap();
+
+        assert!(db.meta(JSON_MIGRATED).unwrap().is_some());
+        assert_eq!(db.load_channels().unwrap()[0].name(), "Channel");
+        let history: Vec<serde_json::Value> = db.load_history().unwrap();
+        assert_eq!(history[0]["title"], "Podcast");
+
+        // a broken DB file is not marked as migrated
+        let (mut broken, broken_dir) = open_temp("migration_broken");
+        assert!(broken.migrate_from_json(Some("{"), None).is_err());
+        assert!(broken.meta(JSON_MIGRATED).unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&broken_dir).unwrap();
+    }
+
+    #[test]
+    fn raises_older_versions() {
+        let (db, dir) = open_temp("older");
+        db.conn
+            .execute("UPDATE meta SET value = '0' WHERE key = ?1", [DB_VERSION_KEY])
+            .unwrap();
+        drop(db);
+
+        let db = Database::open_at(&dir.join("db.sqlite")).unwrap();
+        assert_eq!(db.meta(DB_VERSION_KEY).unwrap(), Some(DB_VERSION.to_string()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_newer_databases() {
+        let (db, dir) = open_temp("newer");
+        db.conn
+            .execute("UPDATE meta SET value = '99' WHERE key = ?1", [DB_VERSION_KEY])
+            .unwrap();
+        drop(db);
+
+        let error = Database::open_at(&dir.join("db.sqlite")).err().unwrap();
+        assert!(matches!(error, NewerDbVersion(99, DB_VERSION)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
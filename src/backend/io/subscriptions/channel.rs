@@ -1,5 +1,5 @@
-use super::{date_always, Date, SubscriptionItem};
-use crate::backend::SortingMethodVideos;
+use super::{date_always, Date, SubscriptionItem, SubscriptionKind};
+use crate::backend::{data::filter::FilterRules, io::config::Config, SortingMethodVideos};
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -17,7 +17,15 @@ pub struct ChannelSubscription {
     #[serde(default)]
     sort_by: SortingMethodVideos,
     #[serde(default)]
+    filter: FilterRules,
+    #[serde(default)]
+    hide_shorts: Option<bool>,
+    #[serde(default)]
+    hide_upcoming: Option<bool>,
+    #[serde(default)]
     download: bool,
+    #[serde(default)]
+    kind: SubscriptionKind,
 }
 
 impl Default for ChannelSubscription {
@@ -30,7 +38,11 @@ impl Default for ChannelSubscription {
             tag: "Interresting".to_string(),
             sort_by: SortingMethodVideos::default(),
             block_regex: None,
+            filter: FilterRules::default(),
+            hide_shorts: None,
+            hide_upcoming: None,
             download: false,
+            kind: SubscriptionKind::default(),
         }
     }
 }
@@ -55,4 +67,19 @@ impl SubscriptionItem for ChannelSubscription {
     fn block_regex(&self) -> &Option<String> {
         &self.block_regex
     }
+    fn filter(&self) -> &FilterRules {
+        &self.filter
+    }
+    fn hide_shorts(&self) -> Option<bool> {
+        self.hide_shorts
+    }
+    fn hide_upcoming(&self) -> Option<bool> {
+        self.hide_upcoming
+    }
+    fn feed_urls(&self, config: &Config) -> Vec<String> {
+        vec![self.kind.feed_url(&self.url, config)]
+    }
+    fn twitch_channel(&self) -> Option<String> {
+        self.kind.twitch_channel(&self.url)
+    }
 }
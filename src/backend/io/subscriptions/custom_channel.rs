@@ -1,5 +1,5 @@
 use super::{date_always, Date, SubscriptionItem};
-use crate::backend::SortingMethodVideos;
+use crate::backend::{data::filter::FilterRules, io::config::Config, SortingMethodVideos};
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +14,12 @@ pub struct CustomChannelSubscription {
     tag: String,
     #[serde(default)]
     sort_by: SortingMethodVideos,
+    #[serde(default)]
+    filter: FilterRules,
+    #[serde(default)]
+    hide_shorts: Option<bool>,
+    #[serde(default)]
+    hide_upcoming: Option<bool>,
 }
 
 impl SubscriptionItem for CustomChannelSubscription {
@@ -36,4 +42,16 @@ impl SubscriptionItem for CustomChannelSubscription {
     fn block_regex(&self) -> &Option<String> {
         &self.block_regex
     }
+    fn filter(&self) -> &FilterRules {
+        &self.filter
+    }
+    fn hide_shorts(&self) -> Option<bool> {
+        self.hide_shorts
+    }
+    fn hide_upcoming(&self) -> Option<bool> {
+        self.hide_upcoming
+    }
+    fn feed_urls(&self, _config: &Config) -> Vec<String> {
+        self.urls.clone()
+    }
 }
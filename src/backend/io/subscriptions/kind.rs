@@ -0,0 +1,178 @@
+use crate::backend::io::config::Config;
+use log::*;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// Platform of a subscription, decides how the feed url is built from `url`
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SubscriptionKind {
+    /// `url` already points to an atom, rss or json feed
+    #[default]
+    Feed,
+    /// channel or account page on any PeerTube instance,
+    /// e.g. `https://framatube.org/c/framasoft` or `https://framatube.org/a/framasoft`
+    Peertube,
+    /// Odysee channel page, e.g. `https://odysee.com/@Odysee:8` or `lbry://@Odysee:8`
+    Odysee,
+    /// Twitch channel page or name, e.g. `https://www.twitch.tv/gamesdonequick`.
+    /// Past broadcasts are read through the rss bridge set in the config
+    Twitch,
+}
+
+impl SubscriptionKind {
+    /// Feed url for the page `url`. Urls that can not be understood are used as they are.
+    pub(crate) fn feed_url(&self, url: &str, config: &Config) -> String {
+        let feed_url = match self {
+            SubscriptionKind::Feed => return url.to_string(),
+            SubscriptionKind::Peertube => peertube_feed_url(url),
+            SubscriptionKind::Odysee => odysee_feed_url(url),
+            SubscriptionKind::Twitch => twitch_name(url)
+                .map(|name| config.twitch_rss_bridge.replace("{channel}", &name)),
+        };
+
+        feed_url.unwrap_or_else(|| {
+            warn!("Could not build {:?} feed url from {}, using it as is", self, url);
+            url.to_string()
+        })
+    }
+
+    /// Name of the twitch channel, if this is a twitch subscription
+    pub(crate) fn twitch_channel(&self, url: &str) -> Option<String> {
+        match self {
+            SubscriptionKind::Twitch => twitch_name(url),
+            _ => None,
+        }
+    }
+}
+
+/// `https://www.twitch.tv/name/videos` and plain `name` are both accepted
+fn twitch_name(url: &str) -> Option<String> {
+    if !url.contains('/') && !url.contains(':') {
+        return Some(url.trim().to_lowercase()).filter(|name| !name.is_empty());
+    }
+
+    let url = Url::parse(url).ok()?;
+    if !url.host_str()?.ends_with("twitch.tv") {
+        return None;
+    }
+
+    let name = url.path_segments()?.find(|s| !s.is_empty())?;
+    Some(name.to_lowercase())
+}
+
+/// PeerTube instances publish the videos of a channel or an account at
+/// `/feeds/videos.xml?videoChannelName=...` or `?accountName=...`
+fn peertube_feed_url(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+
+    // already a feed
+    if url.path().starts_with("/feeds/") {
+        return Some(url.to_string());
+    }
+
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+    let parameter = match segments.next()? {
+        "c" | "video-channels" => "videoChannelName",
+        "a" | "accounts" => "accountName",
+        _ => return None,
+    };
+    let name = segments.next()?;
+
+    let mut feed = url.clone();
+    feed.set_path("/feeds/videos.xml");
+    feed.set_fragment(None);
+    feed.query_pairs_mut().clear().append_pair(parameter, name);
+
+    Some(feed.to_string())
+}
+
+/// Odysee serves a rss feed for every channel at `https://odysee.com/$/rss/@name:id`
+fn odysee_feed_url(url: &str) -> Option<String> {
+    let channel = match url.strip_prefix("lbry://") {
+        Some(rest) => rest.split('/').next()?.to_string(),
+        None => {
+            let url = Url::parse(url).ok()?;
+            if url.host_str()?.trim_start_matches("www.") != "odysee.com" {
+                return None;
+            }
+            url.path_segments()?.find(|s| s.starts_with('@'))?.to_string()
+        }
+    };
+
+    // odysee urls use `:` and `#` as claim id separator
+    let channel = channel.replace('#', ":");
+    if !channel.starts_with('@') || channel.len() < 2 {
+        return None;
+    }
+
+    Some(format!("https://odysee.com/$/rss/{}", channel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_url(kind: SubscriptionKind, url: &str) -> String {
+        kind.feed_url(url, &Config::default())
+    }
+
+    #[test]
+    fn peertube_urls() {
+        let kind = SubscriptionKind::Peertube;
+
+        assert_eq!(
+            feed_url(kind, "https://framatube.org/c/framasoft_channel/videos"),
+            "https://framatube.org/feeds/videos.xml?videoChannelName=framasoft_channel"
+        );
+        assert_eq!(
+            feed_url(kind, "https://framatube.org/a/framasoft"),
+            "https://framatube.org/feeds/videos.xml?accountName=framasoft"
+        );
+        assert_eq!(
+            feed_url(kind, "https://tube.example.org/video-channels/news@other.example.org"),
+            "https://tube.example.org/feeds/videos.xml?videoChannelName=news%40other.example.org"
+        );
+
+        // feeds and unknown pages are left alone
+        let feed = "https://framatube.org/feeds/videos.xml?videoChannelId=3";
+        assert_eq!(feed_url(kind, feed), feed);
+        assert_eq!(feed_url(kind, "https://framatube.org/w/abc"), "https://framatube.org/w/abc");
+    }
+
+    #[test]
+    fn odysee_urls() {
+        let kind = SubscriptionKind::Odysee;
+
+        assert_eq!(
+            feed_url(kind, "https://odysee.com/@Odysee:8"),
+            "https://odysee.com/$/rss/@Odysee:8"
+        );
+        assert_eq!(
+            feed_url(kind, "https://odysee.com/@Odysee:8/some-video:2?r=x"),
+            "https://odysee.com/$/rss/@Odysee:8"
+        );
+        assert_eq!(feed_url(kind, "lbry://@Odysee#8"), "https://odysee.com/$/rss/@Odysee:8");
+
+        assert_eq!(feed_url(kind, "https://example.com/@Odysee"), "https://example.com/@Odysee");
+    }
+
+    #[test]
+    fn twitch_urls() {
+        let kind = SubscriptionKind::Twitch;
+
+        assert_eq!(
+            feed_url(kind, "https://www.twitch.tv/GamesDoneQuick/videos"),
+            "https://twitchrss.appspot.com/vod/gamesdonequick"
+        );
+        assert_eq!(feed_url(kind, "gamesdonequick"), "https://twitchrss.appspot.com/vod/gamesdonequick");
+        assert_eq!(kind.twitch_channel("https://m.twitch.tv/esl_sc2").as_deref(), Some("esl_sc2"));
+        assert_eq!(SubscriptionKind::Feed.twitch_channel("esl_sc2"), None);
+    }
+
+    #[test]
+    fn feed_urls_are_unchanged() {
+        let url = "https://www.youtube.com/feeds/videos.xml?channel_id=UCBa659QWEk1AI4Tg--mrJ2A";
+        assert_eq!(feed_url(SubscriptionKind::Feed, url), url);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::backend::{
-    io::{read_config, FileType::SubscriptionsFile},
-    Error::ParseSubscription,
+    data::filter::{Filter, FilterRules},
+    io::{config::Config, read_config, FileType::SubscriptionsFile},
+    Error::{InvalidRegex, ParseSubscription},
     Result, SortingMethodVideos,
 };
 use channel::ChannelSubscription;
@@ -8,8 +9,11 @@ use chrono::Weekday;
 use custom_channel::CustomChannelSubscription;
 use serde::{Deserialize, Serialize};
 
+pub(crate) use kind::SubscriptionKind;
+
 mod channel;
 mod custom_channel;
+mod kind;
 
 /// Trait for all channel types
 pub(crate) trait SubscriptionItem {
@@ -19,6 +23,17 @@ pub(crate) trait SubscriptionItem {
     fn name(&self) -> String;
     fn sorting_method(&self) -> SortingMethodVideos;
     fn block_regex(&self) -> &Option<String>;
+    fn filter(&self) -> &FilterRules;
+    /// overrides `hide_shorts` of the config
+    fn hide_shorts(&self) -> Option<bool>;
+    /// overrides `hide_upcoming` of the config
+    fn hide_upcoming(&self) -> Option<bool>;
+    /// urls of the feeds that are downloaded for this item
+    fn feed_urls(&self, config: &Config) -> Vec<String>;
+    /// twitch channel whose live status is checked
+    fn twitch_channel(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Default value for date always
@@ -49,10 +64,31 @@ impl Subscriptions {
     pub(crate) fn read() -> Result<Self> {
         let config_file = read_config(SubscriptionsFile);
 
-        match serde_yaml::from_str::<Self>(&config_file) {
-            Ok(file) => Ok(file),
-            Err(error) => Err(ParseSubscription(error)),
+        let subs = match serde_yaml::from_str::<Self>(&config_file) {
+            Ok(file) => file,
+            Err(error) => return Err(ParseSubscription(error)),
+        };
+
+        subs.validate()?;
+        Ok(subs)
+    }
+
+    /// Compile every regex once, so that typos are reported before anything is fetched
+    fn validate(&self) -> Result<()> {
+        let items = self
+            .channels
+            .iter()
+            .map(|item| item as &dyn SubscriptionItem)
+            .chain(self.custom_channels.iter().map(|item| item as &dyn SubscriptionItem));
+
+        for item in items {
+            if let Err(error) = Filter::new(&[item.filter()], item.block_regex().as_deref()) {
+                let name = if item.name().is_empty() { item.id() } else { item.name() };
+                return Err(InvalidRegex(format!("subscription '{}'", name), error));
+            }
         }
+
+        Ok(())
     }
 
     /// checks wheather the url file contains a channel with the given id
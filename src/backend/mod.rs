@@ -4,12 +4,16 @@ pub(crate) mod data;
 pub mod draw;
 pub(super) mod io;
 pub(super) mod dearrow;
+pub(super) mod http;
+pub(crate) mod profile_switcher;
 
 use serde::{Deserialize, Serialize};
 use tui::widgets::ListItem;
 
 use std::{
+    fmt,
     io::{stdin, stdout, Stdout},
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 use termion::{
@@ -88,6 +92,34 @@ impl Default for SortingMethodChannels {
     }
 }
 
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum DetailPanePosition {
+    #[default]
+    Right,
+    Bottom,
+}
+
+/// What happens to stored videos that do not pass the filters anymore
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum StoredVideosFilter {
+    /// keep them visible
+    Off,
+    /// hide them; they come back once the rules allow them again
+    #[default]
+    Hide,
+    Delete,
+}
+
+/// Where channels, videos and the history are stored
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum Storage {
+    /// `db.json` and `history.json`, rewritten on every change
+    #[default]
+    Json,
+    /// `db.sqlite`, marks and channel updates only touch their rows
+    Sqlite,
+}
+
 #[derive(PartialEq)]
 pub enum Action {
     Mark(bool),
@@ -99,12 +131,24 @@ pub enum Action {
     PrevChannel,
     Open,
     SetVideoFav,
+    ToggleDetails,
+    ScrollDetailsDown,
+    ScrollDetailsUp,
+    /// open (or close) the profile switcher
+    SwitchProfile,
+    /// move the focus to the playback history, or back
+    FocusHistory,
+    RemoveFromHistory,
+    ClearHistory,
+    /// open (or close) the statistics
+    ToggleStats,
 }
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum Screen {
     Channels,
     Videos,
+    PlaybackHistory,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -127,6 +171,50 @@ pub enum Error {
     ParseConfig(serde_yaml::Error),
     ParseDB(serde_json::Error),
     ParseSubscription(serde_yaml::Error),
+    HttpClient(reqwest::Error),
+    InvalidHeader(String),
+    /// name of the subscription (or `config`) and the regex error
+    InvalidRegex(String, String),
+    WriteFile(PathBuf, std::io::Error),
+    #[cfg(feature = "sqlite")]
+    Database(String),
+    /// version of the file and the newest version this build can read
+    NewerDbVersion(u64, u64),
+    /// lock file held by another instance
+    AlreadyRunning(PathBuf),
+    /// state file of `tyt state import/export` and what is wrong with it
+    StateFile(PathBuf, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ParseConfig(error) => write!(f, "Could not parse config: {}", error),
+            Error::ParseDB(error) => write!(f, "Could not parse DB: {}", error),
+            Error::ParseSubscription(error) => write!(f, "Could not parse subscriptions: {}", error),
+            Error::HttpClient(error) => write!(f, "Could not set up http client: {}", error),
+            Error::InvalidHeader(name) => write!(f, "Invalid extra header: {}", name),
+            Error::InvalidRegex(name, error) => write!(f, "Invalid regex in {}: {}", name, error),
+            Error::WriteFile(path, error) => {
+                write!(f, "Could not write {}: {}", path.display(), error)
+            }
+            #[cfg(feature = "sqlite")]
+            Error::Database(error) => write!(f, "Database error: {}", error),
+            Error::NewerDbVersion(version, supported) => write!(
+                f,
+                "DB was written by a newer version of tyt (version {}, this build supports up to {}), refusing to touch it",
+                version, supported
+            ),
+            Error::StateFile(path, error) => {
+                write!(f, "Could not read state file {}: {}", path.display(), error)
+            }
+            Error::AlreadyRunning(path) => write!(
+                f,
+                "tyt is already running (lock on {}), close the other instance first",
+                path.display()
+            ),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
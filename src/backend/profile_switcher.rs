@@ -0,0 +1,70 @@
+use crate::backend::io::{current_profile, list_profiles};
+use std::iter::once;
+use tui::{
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{ListItem, ListState},
+};
+
+const DEFAULT_PROFILE_NAME: &str = "default";
+
+/// Popup to pick another profile; tyt is restarted with the chosen one
+pub(crate) struct ProfileSwitcher {
+    /// `None` is the default profile
+    profiles: Vec<Option<String>>,
+    state: ListState,
+}
+
+impl ProfileSwitcher {
+    pub(crate) fn new() -> Self {
+        let profiles: Vec<Option<String>> = once(None).chain(list_profiles().into_iter().map(Some)).collect();
+
+        let current = profiles
+            .iter()
+            .position(|profile| profile.as_deref() == current_profile())
+            .unwrap_or(0);
+
+        let mut state = ListState::default();
+        state.select(Some(current));
+
+        Self { profiles, state }
+    }
+
+    pub(crate) fn next(&mut self) {
+        let i = self.state.selected().map_or(0, |i| (i + 1).min(self.profiles.len() - 1));
+        self.state.select(Some(i));
+    }
+
+    pub(crate) fn prev(&mut self) {
+        let i = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(i));
+    }
+
+    /// The selected profile, if it is not the one already in use
+    pub(crate) fn selected_other(&self) -> Option<Option<String>> {
+        let profile = self.profiles.get(self.state.selected()?)?;
+        (profile.as_deref() != current_profile()).then(|| profile.clone())
+    }
+
+    pub(crate) fn state_mut(&mut self) -> &mut ListState {
+        &mut self.state
+    }
+
+    pub(crate) fn to_list_items(&self) -> Vec<ListItem<'static>> {
+        self.profiles
+            .iter()
+            .map(|profile| {
+                let name = profile.clone().unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string());
+
+                if profile.as_deref() == current_profile() {
+                    ListItem::new(Line::from(vec![
+                        Span::styled(name, Style::default().fg(Color::Yellow)),
+                        Span::styled(" (current)", Style::default().fg(Color::DarkGray)),
+                    ]))
+                } else {
+                    ListItem::new(Line::from(Span::raw(name)))
+                }
+            })
+            .collect()
+    }
+}
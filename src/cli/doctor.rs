@@ -0,0 +1,77 @@
+use crate::backend::{
+    data::Fetcher,
+    io::{
+        config::Config,
+        subscriptions::{SubscriptionItem, Subscriptions},
+    },
+    Result,
+};
+use std::sync::mpsc::channel;
+use threadpool::ThreadPool;
+
+/// Download every subscribed url once and report which ones are broken.
+/// Returns whether all urls could be fetched.
+pub(crate) fn run() -> Result<bool> {
+    let config = Config::read()?;
+    let subs = Subscriptions::read()?;
+    let fetcher = Fetcher::new(&config)?;
+
+    let mut checks = Vec::new();
+    for item in subs.channels.iter() {
+        checks.push((display_name(item), item.feed_urls(&config)));
+    }
+    for item in subs.custom_channels.iter() {
+        checks.push((display_name(item), item.feed_urls(&config)));
+    }
+
+    let pool = ThreadPool::new(config.fetch_workers.max(1));
+    let (sender, receiver) = channel();
+
+    for (i, (_, urls)) in checks.iter().enumerate() {
+        for (j, url) in urls.iter().enumerate() {
+            let sender = sender.clone();
+            let fetcher = fetcher.clone();
+            let url = url.clone();
+            pool.execute(move || {
+                let _ = sender.send((i, j, fetcher.check(&url)));
+            });
+        }
+    }
+    drop(sender);
+
+    let mut results: Vec<_> = receiver.iter().collect();
+    results.sort_by_key(|(i, j, _)| (*i, *j));
+
+    let mut num_failed = 0;
+    let mut current = None;
+
+    for (i, j, result) in results.into_iter() {
+        let (name, urls) = &checks[i];
+
+        if current != Some(i) {
+            println!("{}", name);
+            current = Some(i);
+        }
+
+        match result {
+            Ok(num_videos) => println!("  ok      {} ({} videos)", urls[j], num_videos),
+            Err(error) => {
+                num_failed += 1;
+                println!("  FAILED  {}\n          {}", urls[j], error);
+            }
+        }
+    }
+
+    let num_urls: usize = checks.iter().map(|(_, urls)| urls.len()).sum();
+    println!("\n{} of {} urls failed", num_failed, num_urls);
+
+    Ok(num_failed == 0)
+}
+
+fn display_name(item: &dyn SubscriptionItem) -> String {
+    if item.name().is_empty() {
+        item.id()
+    } else {
+        item.name()
+    }
+}
@@ -0,0 +1,209 @@
+pub(crate) mod doctor;
+pub(crate) mod state;
+pub(crate) mod stats;
+
+use crate::backend::io::is_valid_profile_name;
+use std::{
+    env,
+    os::unix::process::CommandExt,
+    path::PathBuf,
+    process,
+};
+
+pub(crate) const USAGE: &str = "Usage: tyt [OPTIONS] [COMMAND]
+
+Commands:
+    doctor               Check every subscription and print what is broken
+    state export [FILE]  Write marked, fav and open duration of all videos to FILE
+                         (or stdout), merged with the states already in FILE
+    state import FILE    Take over all states from FILE that are newer than the local ones
+    stats [--json]       Print watch statistics, backlog and channels without recent uploads
+    help                 Print this message
+
+Options:
+    --config-dir DIR    Keep all files in DIR instead of the XDG directories.
+                        Can also be set with the TYT_HOME environment variable.
+    --profile NAME      Use the subscriptions, DB and history of profile NAME.
+
+Without a command the interactive interface is started.";
+
+const HOME_VAR: &str = "TYT_HOME";
+
+/// What tyt was asked to do on the command line
+#[derive(Debug, PartialEq)]
+pub(crate) enum Command {
+    Tui,
+    Doctor,
+    StateExport(Option<PathBuf>),
+    StateImport(PathBuf),
+    Stats { json: bool },
+    Help,
+}
+
+pub(crate) struct Args {
+    pub(crate) command: Command,
+    /// directory for all files, from `--config-dir` or `TYT_HOME`
+    pub(crate) home: Option<PathBuf>,
+    /// `None` is the default profile
+    pub(crate) profile: Option<String>,
+}
+
+impl Args {
+    /// Parse the arguments tyt was started with
+    pub(crate) fn parse() -> Result<Self, String> {
+        let mut args = Self::parse_from(env::args().skip(1))?;
+        if args.home.is_none() {
+            args.home = env::var_os(HOME_VAR)
+                .filter(|home| !home.is_empty())
+                .map(PathBuf::from);
+        }
+        Ok(args)
+    }
+
+    fn parse_from(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut home = None;
+        let mut profile = None;
+
+        while let Some(arg) = args.next() {
+            // `--option value` and `--option=value`
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => (option, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = |what: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or(format!("{} needs {}", option, what))
+            };
+
+            match option {
+                "--config-dir" => {
+                    home = Some(PathBuf::from(value("a directory")?));
+                    continue;
+                }
+                "--profile" => {
+                    let name = value("a name")?;
+                    if !is_valid_profile_name(&name) {
+                        return Err(format!("Invalid profile name: {}", name));
+                    }
+                    profile = Some(name);
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        let positional: Vec<&str> = positional.iter().map(String::as_str).collect();
+        let (command, rest) = match positional.as_slice() {
+            [] => (Command::Tui, &[][..]),
+            ["doctor", rest @ ..] => (Command::Doctor, rest),
+            ["help" | "-h" | "--help", rest @ ..] => (Command::Help, rest),
+            ["state", "export", rest @ ..] => match rest {
+                [file, rest @ ..] => (Command::StateExport(Some(PathBuf::from(file))), rest),
+                [] => (Command::StateExport(None), rest),
+            },
+            ["state", "import", file, rest @ ..] => (Command::StateImport(PathBuf::from(file)), rest),
+            ["state", "import"] => return Err("state import needs a file".to_string()),
+            ["state", ..] => return Err("state needs export or import".to_string()),
+            ["stats", "--json", rest @ ..] => (Command::Stats { json: true }, rest),
+            ["stats", rest @ ..] => (Command::Stats { json: false }, rest),
+            [other, ..] => return Err(format!("Unknown command: {}", other)),
+        };
+
+        if let Some(arg) = rest.first() {
+            return Err(format!("Unexpected argument: {}", arg));
+        }
+
+        Ok(Self {
+            command,
+            home,
+            profile,
+        })
+    }
+}
+
+/// Replace this process by a new tyt with `profile`; only returns if that failed
+pub(crate) fn restart_with_profile(home: Option<&PathBuf>, profile: Option<&str>) -> std::io::Error {
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(error) => return error,
+    };
+
+    let mut command = process::Command::new(exe);
+    if let Some(home) = home {
+        command.arg("--config-dir").arg(home);
+    }
+    if let Some(profile) = profile {
+        command.arg("--profile").arg(profile);
+    }
+
+    command.exec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn config_dir_before_and_after_command() {
+        let args = parse(&["--config-dir", "/tmp/profile", "doctor"]).unwrap();
+        assert_eq!(args.command, Command::Doctor);
+        assert_eq!(args.home, Some(PathBuf::from("/tmp/profile")));
+
+        let args = parse(&["doctor", "--config-dir=/tmp/profile"]).unwrap();
+        assert_eq!(args.home, Some(PathBuf::from("/tmp/profile")));
+
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.command, Command::Tui);
+        assert_eq!(args.home, None);
+    }
+
+    #[test]
+    fn state_commands() {
+        let args = parse(&["state", "export"]).unwrap();
+        assert_eq!(args.command, Command::StateExport(None));
+
+        let args = parse(&["state", "export", "/sync/tyt.json", "--profile", "work"]).unwrap();
+        assert_eq!(args.command, Command::StateExport(Some(PathBuf::from("/sync/tyt.json"))));
+        assert_eq!(args.profile.as_deref(), Some("work"));
+
+        let args = parse(&["state", "import", "/sync/tyt.json"]).unwrap();
+        assert_eq!(args.command, Command::StateImport(PathBuf::from("/sync/tyt.json")));
+
+        assert!(parse(&["state", "import"]).is_err());
+        assert!(parse(&["state"]).is_err());
+        assert!(parse(&["state", "export", "a", "b"]).is_err());
+    }
+
+    #[test]
+    fn stats() {
+        assert_eq!(parse(&["stats"]).unwrap().command, Command::Stats { json: false });
+        assert_eq!(parse(&["stats", "--json"]).unwrap().command, Command::Stats { json: true });
+        assert!(parse(&["stats", "--csv"]).is_err());
+    }
+
+    #[test]
+    fn profile() {
+        let args = parse(&["--profile", "work"]).unwrap();
+        assert_eq!(args.command, Command::Tui);
+        assert_eq!(args.profile.as_deref(), Some("work"));
+
+        let args = parse(&["--profile=talks-2024", "doctor"]).unwrap();
+        assert_eq!(args.command, Command::Doctor);
+        assert_eq!(args.profile.as_deref(), Some("talks-2024"));
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(parse(&["--config-dir"]).is_err());
+        assert!(parse(&["update"]).is_err());
+        assert!(parse(&["doctor", "help"]).is_err());
+        assert!(parse(&["--profile", "../work"]).is_err());
+        assert!(parse(&["--profile"]).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::backend::{
+    data::{channel_list::ChannelList, watch_state::StateFile},
+    io::{config::Config, lock::InstanceLock, write_file},
+    Error::StateFile as StateFileError,
+    Result,
+};
+use std::{fs, io::ErrorKind, path::Path};
+
+/// Write the watched state of all videos to `path`, or stdout without a path.
+/// States already in the file are merged, so several machines can share one file.
+pub(crate) fn export(path: Option<&Path>) -> Result<()> {
+    let config = Config::read()?;
+    let channel_list = ChannelList::load(&config)?;
+    let mut state = StateFile::from_channels(&channel_list);
+
+    let Some(path) = path else {
+        println!("{}", state.to_json()?);
+        return Ok(());
+    };
+
+    if let Some(known) = read(path)? {
+        state.merge(known);
+    }
+    write_file(path, &state.to_json()?)?;
+
+    println!("Exported the state of {} videos to {}", state.len(), path.display());
+    Ok(())
+}
+
+/// Take over all states from `path` that are newer than the local ones
+pub(crate) fn import(path: &Path) -> Result<()> {
+    // the running interface would save over the imported states
+    let _lock = InstanceLock::acquire()?;
+
+    let state = read(path)?.ok_or_else(|| StateFileError(path.to_path_buf(), "file not found".to_string()))?;
+
+    let config = Config::read()?;
+    let mut channel_list = ChannelList::load(&config)?;
+    let changed = state.apply(&mut channel_list);
+
+    if changed > 0 {
+        channel_list.save(config.storage)?;
+    }
+
+    println!("Imported {} changed videos from {}", changed, path.display());
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Option<StateFile>> {
+    let error = |error: String| StateFileError(path.to_path_buf(), error);
+
+    match fs::read_to_string(path) {
+        Ok(json) => StateFile::from_json(&json).map(Some).map_err(error),
+        Err(io_error) if io_error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(io_error) => Err(error(io_error.to_string())),
+    }
+}
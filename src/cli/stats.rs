@@ -0,0 +1,37 @@
+use crate::backend::{
+    data::{channel_list::ChannelList, stats::Stats},
+    io::{config::Config, history::History, open_counts::OpenCounts},
+    Result,
+};
+
+/// Print the statistics as text, or as json to graph them elsewhere
+pub(crate) fn run(json: bool) -> Result<()> {
+    let config = Config::read()?;
+    let channel_list = ChannelList::load(&config)?;
+    let history = History::load(config.storage, config.max_history);
+
+    let open_counts = OpenCounts::load(history.entries());
+
+    let stats = Stats::collect(&channel_list, &open_counts, history.entries(), config.stale_after_months);
+
+    if json {
+        println!("{}", stats.to_json());
+        return Ok(());
+    }
+
+    for (i, (heading, rows)) in stats.report().into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", heading);
+
+        if rows.is_empty() {
+            println!("  none");
+        }
+        for row in rows {
+            println!("  {}", row);
+        }
+    }
+
+    Ok(())
+}
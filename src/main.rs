@@ -1,14 +1,18 @@
 mod backend;
+mod cli;
 mod events;
 
 use std::fs::File;
 use std::sync::mpsc::channel;
 use std::sync::{RwLock, Arc};
+use std::{thread, time::Duration};
 
 use crate::backend::{core::Core, draw::draw, data::Data, Action::*, Error, Screen::*};
+use crate::cli::{Args, Command, USAGE};
 use crate::notification::*;
 use arboard::Clipboard;
 use backend::data::downloader::Downloader;
+use backend::io::{self, lock::InstanceLock};
 use events::*;
 use log::LevelFilter;
 use simplelog::{ConfigBuilder, WriteLogger};
@@ -19,6 +23,16 @@ mod notification;
 
 fn main() -> Result<(), Error> {
 
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    io::init_dirs(args.home.clone(), args.profile.clone());
+
     // init loggin
     let loggin_config = ConfigBuilder::new()
         .add_filter_ignore("reqwest".to_string())
@@ -28,10 +42,58 @@ fn main() -> Result<(), Error> {
     WriteLogger::init(
         LevelFilter::Debug,
         loggin_config,
-        File::create("debug.log").unwrap(),
+        File::create(io::log_path()).unwrap(),
     ).unwrap();
 
-    let core = match Core::load() {
+    // a separate directory is never mixed with the files in the default place
+    if args.home.is_none() {
+        io::migrate_legacy_files();
+    }
+
+    match args.command {
+        Command::Tui => {}
+        Command::Help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Command::Doctor => {
+            if !cli::doctor::run()? {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::StateExport(ref path) => {
+            if let Err(error) = cli::state::export(path.as_deref()) {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Stats { json } => {
+            cli::stats::run(json)?;
+            return Ok(());
+        }
+        Command::StateImport(ref path) => {
+            if let Err(error) = cli::state::import(path) {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+    }
+
+    // held until tyt exits
+    let _lock = match InstanceLock::acquire() {
+        Ok(lock) => lock,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let (status_sender, status_receiver) = channel();
+
+    let core = match Core::load(status_sender.clone()) {
         Ok(core) => core,
         Err(error) => {
             return Err(error);
@@ -43,8 +105,7 @@ fn main() -> Result<(), Error> {
     let events = Events::new(); // event queue
     let mut tick_counter = 0;
 
-    let (status_sender, status_receiver) = channel();
-    let data = Data::init(status_sender.clone());
+    let data = Data::init(status_sender.clone(), &core.read().unwrap().config)?;
 
     let downloader = Downloader::new(status_sender);
 
@@ -53,15 +114,20 @@ fn main() -> Result<(), Error> {
     }
 
 
-    loop {
+    let switch_to_profile = loop {
         let event = events.next();
 
-        if let Ok(c) = data.try_recv() {
+        if let Ok((c, validators)) = data.try_recv() {
             let core_write_lock = core.try_write();
             if let Ok(mut core) = core_write_lock {
                 downloader.sync_channel(c.clone());
+                let id = c.id().clone();
                 core.update_channel(c);
-                core.save();
+                if core.save_channel(&id) {
+                    if let Some(validators) = validators {
+                        data.save_validators(&id, validators);
+                    }
+                }
             }
         }
 
@@ -74,8 +140,12 @@ fn main() -> Result<(), Error> {
                     Key::Char('q') => {
                         // ----------------- close -----------------------
                         match core.get_current_screen() {
-                            Channels => break,
-                            Videos => {
+                            Channels if core.popup_open() => {
+                                core.action(Leave);
+                                draw(core_pointer);
+                            }
+                            Channels => break None,
+                            Videos | PlaybackHistory => {
                                 core.action(Leave);
                                 draw(core_pointer);
                             }
@@ -84,8 +154,13 @@ fn main() -> Result<(), Error> {
                     Key::Esc | Key::Char('h') | Key::Left => {
                         // ---------------------- back --------------
                         match core.get_current_screen() {
-                            Channels => {}
-                            Videos => {
+                            Channels if core.popup_open() => {
+                                core.action(Leave);
+                            }
+                            Channels => {
+                                core.show_details = false;
+                            }
+                            Videos | PlaybackHistory => {
                                 core.action(Leave);
                             }
                         }
@@ -110,16 +185,20 @@ fn main() -> Result<(), Error> {
                     }
                     Key::Char('f') => {
                         match core.get_current_screen() {
-                            Channels => {}
+                            Channels | PlaybackHistory => {}
                             Videos => {
                                 core.action(SetVideoFav);
                             }
                         }
                         draw(core_pointer);
                     }
+                    Key::Char('o') if *core.get_current_screen() == PlaybackHistory => {
+                        core.action(Open);
+                        draw(core_pointer);
+                    }
                     Key::Char('\n') | Key::Char('l') | Key::Right | Key::Char('o') => {
                         match core.get_current_screen() {
-                            Channels => {
+                            Channels | PlaybackHistory => {
                                 core.action(Enter);
                             }
                             Videos => {
@@ -141,13 +220,45 @@ fn main() -> Result<(), Error> {
                         data.update(&core.config);
                         core.action(Leave);
                     }
+                    Key::Char('i') => {
+                        core.action(ToggleDetails);
+                        draw(core_pointer);
+                    }
+                    Key::Char('J') => {
+                        core.action(ScrollDetailsDown);
+                        draw(core_pointer);
+                    }
+                    Key::Char('K') => {
+                        core.action(ScrollDetailsUp);
+                        draw(core_pointer);
+                    }
+                    Key::Char('P') if *core.get_current_screen() == Channels => {
+                        core.action(SwitchProfile);
+                        draw(core_pointer);
+                    }
+                    Key::Char('S') if *core.get_current_screen() == Channels => {
+                        core.action(ToggleStats);
+                        draw(core_pointer);
+                    }
+                    Key::Char('H') => {
+                        core.action(FocusHistory);
+                        draw(core_pointer);
+                    }
+                    Key::Char('d') if *core.get_current_screen() == PlaybackHistory => {
+                        core.action(RemoveFromHistory);
+                        draw(core_pointer);
+                    }
+                    Key::Char('D') if *core.get_current_screen() == PlaybackHistory => {
+                        core.action(ClearHistory);
+                        draw(core_pointer);
+                    }
                     Key::Char('t') => {
                         // core.set_show_empty(!core.get_show_empty());
                         core.toggle_filter();
                         draw(core_pointer);
                     }
                     Key::Char('c') => match core.get_current_screen() {
-                        Channels => (),
+                        Channels | PlaybackHistory => (),
                         Videos => {
                             let link = core.get_selected_video_link();
                             notify_link(&link);
@@ -179,12 +290,41 @@ fn main() -> Result<(), Error> {
                         tick_counter -= 1
                     }
 
+                    if core.clear_old_status_message() {
+                        changed = true;
+                    }
+
                     if core.terminal.update_size() || changed {
                         draw(core_pointer);
                     }
                 }
             }
+
+            if let Some(profile) = core.switch_to_profile.take() {
+                break Some(profile);
+            }
+        }
+    };
+
+    if let Some(profile) = switch_to_profile {
+        drop(data);
+        drop(_lock);
+
+        // the terminal is restored once the last draw thread let go of the core
+        let mut core = core;
+        loop {
+            match Arc::try_unwrap(core) {
+                Ok(core) => break drop(core),
+                Err(shared) => {
+                    core = shared;
+                    thread::sleep(Duration::from_millis(10));
+                }
+            }
         }
+
+        let error = cli::restart_with_profile(args.home.as_ref(), profile.as_deref());
+        eprintln!("Could not switch profile: {}", error);
+        std::process::exit(1);
     }
 
     Ok(())